@@ -0,0 +1,161 @@
+use crate::edit_plr::{
+    Buff,
+    Plr,
+};
+use crate::error::PlrError;
+use crate::layout::Layout;
+
+// Buff times are saved in game ticks
+pub const TICKS_PER_SECOND: i32 = 60;
+
+// One or more groups of a number and a unit (h, m, s or t for ticks), e.g. "90s", "1h30m" or "600t"; a bare number is seconds
+pub fn parse_duration(s: &str) -> Result<i32, String> {
+    let invalid = || format!("Invalid duration {:?} (expected e.g. 90s, 5m, 1h30m or 600t)", s);
+    if let Ok(seconds) = s.parse::<i32>() {
+        return seconds.checked_mul(TICKS_PER_SECOND).ok_or_else(invalid);
+    }
+    let mut ticks: i32 = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let scale = match c {
+            'h' => 3600 * TICKS_PER_SECOND,
+            'm' => 60 * TICKS_PER_SECOND,
+            's' => TICKS_PER_SECOND,
+            't' => 1,
+            _ => return Err(invalid()),
+        };
+        let n: i32 = number.parse().map_err(|_| invalid())?;
+        ticks = n.checked_mul(scale).and_then(|t| ticks.checked_add(t)).ok_or_else(invalid)?;
+        number.clear();
+    }
+    if !number.is_empty() || s.is_empty() {
+        return Err(invalid());
+    }
+    return Ok(ticks);
+}
+
+pub fn format_duration(ticks: i32) -> String {
+    let seconds = ticks / TICKS_PER_SECOND;
+    let rest = ticks % TICKS_PER_SECOND;
+    let mut out = if seconds >= 3600 {
+        format!("{}h {:02}m {:02}s", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    };
+    if rest != 0 {
+        out.push_str(&format!(" {}t", rest));
+    }
+    return out;
+}
+
+fn invalid(reason: String) -> PlrError {
+    return PlrError::InvalidField { field: "buffs", reason };
+}
+
+impl Plr {
+    pub fn buff_mut(&mut self, buff_type: i32) -> Option<&mut Buff> {
+        return self.buffs.iter_mut().find(|b| b.buff_type == buff_type && !b.is_empty());
+    }
+
+    // Fills the first empty slot; the game keeps at most one entry per buff type
+    pub fn add_buff(&mut self, buff_type: i32, ticks: i32) -> Result<(), PlrError> {
+        if buff_type <= 0 {
+            return Err(invalid(format!("buff type {} is not positive", buff_type)));
+        }
+        if ticks <= 0 {
+            return Err(invalid(format!("duration of {} ticks is not positive", ticks)));
+        }
+        if self.buff_mut(buff_type).is_some() {
+            return Err(invalid(format!("buff {} is already active; extend it instead", buff_type)));
+        }
+        let buff = Buff { buff_type, time: ticks };
+        if let Some(slot) = self.buffs.iter_mut().find(|b| b.is_empty()) {
+            *slot = buff;
+            return Ok(());
+        }
+        let slots = Layout::for_version(self.version)?.buff_slots();
+        if self.buffs.len() >= slots {
+            return Err(invalid(format!("all {} buff slots of a version {} player are taken", slots, self.version)));
+        }
+        self.buffs.push(buff);
+        return Ok(());
+    }
+
+    // Later buffs move up to fill the gap, as they do in game
+    pub fn remove_buff(&mut self, buff_type: i32) -> Result<Buff, PlrError> {
+        let index = self
+            .buffs
+            .iter()
+            .position(|b| b.buff_type == buff_type && !b.is_empty())
+            .ok_or_else(|| invalid(format!("buff {} is not active", buff_type)))?;
+        return Ok(self.buffs.remove(index));
+    }
+
+    pub fn extend_buff(&mut self, buff_type: i32, ticks: i32) -> Result<i32, PlrError> {
+        let buff = self.buff_mut(buff_type).ok_or_else(|| invalid(format!("buff {} is not active", buff_type)))?;
+        buff.time = buff.time.checked_add(ticks).filter(|t| *t > 0).ok_or_else(|| invalid(format!("buff {} cannot last {} ticks more", buff_type, ticks)))?;
+        return Ok(buff.time);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_parse_to_ticks() {
+        for (s, ticks) in [
+            ("90s", Some(90 * 60)),
+            ("1h30m", Some(90 * 60 * 60)),
+            ("600t", Some(600)),
+            ("2m1s30t", Some(121 * 60 + 30)),
+            ("5", Some(5 * 60)),
+            ("", None),
+            ("1h5", None), // The 5 has no unit
+            ("5x", None),
+            ("h", None),
+            ("40000000", None),
+            ("9999h", None),
+            ("35791394s7t", Some(i32::MAX)),
+            ("35791394s8t", None),
+        ] {
+            assert_eq!(parse_duration(s).ok(), ticks, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn durations_format_from_ticks() {
+        for (ticks, s) in [
+            (0, "0s"),
+            (59 * 60, "59s"),
+            (90 * 60, "1m 30s"),
+            (3600 * 60 + 5 * 60, "1h 00m 05s"),
+            (90 * 60 + 15, "1m 30s 15t"),
+            (7, "0s 7t"),
+        ] {
+            assert_eq!(format_duration(ticks), s, "{} ticks", ticks);
+        }
+    }
+
+    #[test]
+    fn add_buff_stops_when_the_slots_are_full() {
+        for (version, slots) in [(251, 22), (279, 44)] {
+            let mut plr = Plr { version, ..Plr::default() };
+            for buff_type in 1..=slots as i32 {
+                plr.add_buff(buff_type, 60).unwrap();
+            }
+            assert!(plr.add_buff(slots as i32 + 1, 60).is_err(), "version {}", version);
+            assert_eq!(plr.buffs.len(), slots);
+            // A freed slot can be used again
+            plr.buffs[3] = Buff::default();
+            plr.add_buff(slots as i32 + 1, 60).unwrap();
+            assert_eq!(plr.buffs[3].buff_type, slots as i32 + 1);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::edit_plr::{
+    CreativePowers,
+    Plr,
+};
+use crate::error::PlrError;
+use crate::layout::{
+    Feature,
+    Layout,
+};
+use crate::storage::Container;
+
+// Saved as a single byte; shown in JSON and on the command line by name. A byte no known game version writes is kept
+// as `Unknown` so the player still round trips.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    #[default]
+    Classic,
+    Mediumcore,
+    Hardcore,
+    Journey,
+    Unknown(u8),
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Classic, Difficulty::Mediumcore, Difficulty::Hardcore, Difficulty::Journey];
+
+    fn name(&self) -> &'static str {
+        return match self {
+            Difficulty::Classic => "classic",
+            Difficulty::Mediumcore => "mediumcore",
+            Difficulty::Hardcore => "hardcore",
+            Difficulty::Journey => "journey",
+            Difficulty::Unknown(_) => "unknown",
+        };
+    }
+
+    pub fn from_byte(b: u8) -> Difficulty {
+        return Difficulty::ALL.get(b as usize).copied().unwrap_or(Difficulty::Unknown(b));
+    }
+
+    pub fn to_byte(self) -> u8 {
+        return match self {
+            Difficulty::Classic => 0,
+            Difficulty::Mediumcore => 1,
+            Difficulty::Hardcore => 2,
+            Difficulty::Journey => 3,
+            Difficulty::Unknown(b) => b,
+        };
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Difficulty::ALL
+            .into_iter()
+            .find(|d| d.name() == s)
+            .ok_or_else(|| format!("Unknown difficulty {:?} (expected one of {})", s, Difficulty::ALL.map(|d| d.name()).join(", ")));
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Difficulty::Unknown(b) => write!(f, "{} ({})", self.name(), b),
+            _ => f.write_str(self.name()),
+        };
+    }
+}
+
+// What a new Journey character starts with: god mode off, far placement on and the spawn rate slider centred
+pub const JOURNEY_POWERS: CreativePowers = CreativePowers {
+    godmode: Some(false),
+    far_placement_range: Some(true),
+    spawn_rate: Some(0.5),
+};
+
+impl Plr {
+    // Changes the difficulty along with the data only Journey players have. Becoming a Journey player sets up the
+    // starting creative powers; leaving Journey mode forgets research and powers and moves an item left in the
+    // research slot into the inventory.
+    pub fn convert_mode(&mut self, to: Difficulty) -> Result<(), PlrError> {
+        if to == self.difficulty {
+            return Ok(());
+        }
+        if to == Difficulty::Journey {
+            if !Layout::for_version(self.version)?.has(Feature::CreativePowers) {
+                return Err(PlrError::InvalidField {
+                    field: "difficulty",
+                    reason: format!("version {} players cannot be Journey mode", self.version),
+                });
+            }
+            self.research.clear();
+            self.creative_powers = JOURNEY_POWERS;
+        } else if self.difficulty == Difficulty::Journey {
+            if let Some(item) = self.temporary_items.creative_sacrifice {
+                self.add_item(Container::Inventory, None, item)?;
+                self.temporary_items.creative_sacrifice = None;
+            }
+            self.research.clear();
+            self.creative_powers = CreativePowers::default();
+        }
+        self.difficulty = to;
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_plr::Item;
+    use crate::research::Research;
+
+    #[test]
+    fn bytes_map_to_difficulties_and_back() {
+        for (b, difficulty) in Difficulty::ALL.into_iter().enumerate() {
+            assert_eq!(Difficulty::from_byte(b as u8), difficulty);
+            assert_eq!(difficulty.to_byte(), b as u8);
+            assert_eq!(difficulty.to_string().parse::<Difficulty>(), Ok(difficulty));
+        }
+        for b in 4..=u8::MAX {
+            assert_eq!(Difficulty::from_byte(b), Difficulty::Unknown(b));
+            assert_eq!(Difficulty::from_byte(b).to_byte(), b);
+        }
+        let json = serde_json::to_string(&Difficulty::Unknown(7)).unwrap();
+        assert_eq!(serde_json::from_str::<Difficulty>(&json).unwrap(), Difficulty::Unknown(7));
+        assert_eq!(serde_json::to_string(&Difficulty::Journey).unwrap(), "\"journey\"");
+    }
+
+    fn player(version: u32, difficulty: Difficulty) -> Plr {
+        let mut research = Research::default();
+        research.set("DirtBlock", 100);
+        return Plr { version, difficulty, research, ..Plr::default() };
+    }
+
+    #[test]
+    fn converting_into_journey_sets_up_powers() {
+        let mut plr = player(279, Difficulty::Classic);
+        plr.convert_mode(Difficulty::Journey).unwrap();
+        assert_eq!(plr.difficulty, Difficulty::Journey);
+        assert_eq!(plr.creative_powers, JOURNEY_POWERS);
+        assert!(plr.research.entries.is_empty());
+
+        let mut old = player(219, Difficulty::Classic);
+        assert!(old.convert_mode(Difficulty::Journey).is_err());
+        assert_eq!(old.difficulty, Difficulty::Classic);
+    }
+
+    #[test]
+    fn converting_out_of_journey_keeps_the_research_slot_item() {
+        let mut plr = player(279, Difficulty::Journey);
+        plr.creative_powers = JOURNEY_POWERS;
+        let sacrifice = Item { id: 8, stack: 5, prefix: 0, favourites: false, unknown: false };
+        plr.temporary_items.creative_sacrifice = Some(sacrifice);
+        plr.convert_mode(Difficulty::Mediumcore).unwrap();
+        assert_eq!(plr.difficulty, Difficulty::Mediumcore);
+        assert_eq!(plr.creative_powers, CreativePowers::default());
+        assert!(plr.research.entries.is_empty());
+        assert!(plr.temporary_items.creative_sacrifice.is_none());
+        assert!(plr.inventory.iter().any(|item| item.id == sacrifice.id && item.stack == sacrifice.stack));
+    }
+}
@@ -1,14 +1,17 @@
-use std::fs::write;
+use std::fmt;
 use std::io::{
+    self,
+    ErrorKind,
     Read,
     Write,
-    Cursor,
-    Seek,
-    SeekFrom,
+};
+use std::path::{
+    Path,
+    PathBuf,
 };
 
 use aes::Aes128;
-use block_padding::NoPadding;
+use block_padding::Pkcs7;
 use cipher::{
     BlockDecryptMut,
     BlockEncryptMut,
@@ -25,370 +28,401 @@ use byteorder::{
 };
 
 use serde::{
+    de,
+    Deserializer,
     Serialize,
+    Serializer,
     Deserialize,
 };
-use serde_json;
-
-pub fn decrypt_plr_aes128cbc(mut data: Vec<u8>, key: &[u8]) -> Vec<u8> {
-    let cipher = Aes128CbcDec::new(key.into(), key.into());
-    return cipher.decrypt_padded_mut::<NoPadding>(data.as_mut_slice()).expect("Error decrypting ciphertext").to_owned();
-}
-
-pub fn encrypt_plr_aes128cbc(mut data: Vec<u8>, key: &[u8]) -> Vec<u8> {
-    let b = 16 - (data.len() & 15);
-    data.extend(vec![u8::try_from(b).expect("Error padding"); b]);
-    let cipher = Aes128CbcEnc::new(key.into(), key.into());
-    return cipher.encrypt_padded_vec_mut::<NoPadding>(&data.as_slice());
-}
-
-pub fn deserialize_raw_to_struct_plr(data: Vec<u8>) -> Plr {
-    let mut reader = Cursor::new(&data);
-
-    let version: u32 = PlrUnpacker::r_u32(&mut reader);
-    let company: String = PlrUnpacker::r_string(&mut reader, 7);
-    let file_type: u8 = PlrUnpacker::r_u8(&mut reader);
-
-    let unknown1 = PlrUnpacker::r_bytes(&mut reader, 12);
-
-    let name_length: u8 = PlrUnpacker::r_u8(&mut reader);
-    let name: String = PlrUnpacker::r_string(&mut reader, name_length as usize);
-    let difficulty: i8 = PlrUnpacker::r_i8(&mut reader);
-    let play_time: i64 = PlrUnpacker::r_i64(&mut reader);
-
-    let hair_style: i32 = PlrUnpacker::r_i32(&mut reader);
-    let hair_dye: u8 = PlrUnpacker::r_u8(&mut reader);
-    let hide_visual: u16 = PlrUnpacker::r_u16(&mut reader);
-    let hide_misc: u8 = PlrUnpacker::r_u8(&mut reader);
-    let gender: u8 = PlrUnpacker::r_u8(&mut reader);
-
-    let stat_life: i32 = PlrUnpacker::r_i32(&mut reader);
-    let stat_life_max: i32 = PlrUnpacker::r_i32(&mut reader);
-    let stat_mana: i32 = PlrUnpacker::r_i32(&mut reader);
-    let stat_mana_max: i32 = PlrUnpacker::r_i32(&mut reader);
-    let extra_accessory: bool = PlrUnpacker::r_bool(&mut reader);
-
-    let done_dundefii_event: bool = PlrUnpacker::r_bool(&mut reader);
-
-    let tax_money: i32 = PlrUnpacker::r_i32(&mut reader);
-
-    let hair_colour: i32 = PlrUnpacker::r_i32(&mut reader);
-    let skin_colour: i32 = PlrUnpacker::r_i32(&mut reader);
-    let eye_colour: i32 = PlrUnpacker::r_i32(&mut reader);
-    let shirt_colour: i32 = PlrUnpacker::r_i32(&mut reader);
-    let undershirt_colour: i32 = PlrUnpacker::r_i32(&mut reader);
-    let pants_colour: i32 = PlrUnpacker::r_i32(&mut reader);
-    let shoes_colour: i32 = PlrUnpacker::r_i32(&mut reader);
-
-    let unknown2 = PlrUnpacker::r_bytes(&mut reader, 10);
-
-    let armor: [Equipment; 3] = [
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)}
-    ];
-    let accessories: [Equipment; 6] = [
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)}
-    ];
-
-    let unknown3 = PlrUnpacker::r_bytes(&mut reader, 20);
-
-    let accessories_vanity: [Equipment; 6] = [
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)}
-    ];
-
-    let unknown4 = PlrUnpacker::r_bytes(&mut reader, 5);
-
-    let dyes: [Equipment; 8] = [
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-        Equipment {id: PlrUnpacker::r_i32(&mut reader), prefix: PlrUnpacker::r_u8(&mut reader)},
-    ];
-
-    let unknown5 = PlrUnpacker::r_bytes(&mut reader, 10);
-
-    let mut inventory1: [Item; 32] = [Item::default(); 32];
-    let mut inventory2: [Item; 26] = [Item::default(); 26];
-    for idx in 0..32 {
-        let id: i32 = PlrUnpacker::r_i32(&mut reader);
-        if id == 0 || id > 5455 {
-            reader.seek(SeekFrom::Current(6)).expect("Error seeking forward in the data");
-        } else {
-            inventory1[idx] = Item {
-                id: id,
-                stack: PlrUnpacker::r_i32(&mut reader),
-                prefix: PlrUnpacker::r_u8(&mut reader),
-                favourites: PlrUnpacker::r_bool(&mut reader)
-            };
-        }
-    }
-    for idx in 0..26 {
-        let id: i32 = PlrUnpacker::r_i32(&mut reader);
-        if id == 0 || id > 5455 {
-            reader.seek(SeekFrom::Current(6)).expect("Error seeking forward in the data");
-        } else {
-            inventory2[idx] = Item {
-                id: id,
-                stack: PlrUnpacker::r_i32(&mut reader),
-                prefix: PlrUnpacker::r_u8(&mut reader),
-                favourites: PlrUnpacker::r_bool(&mut reader)
-            };
-        }
-    }
-
-    let plr = Plr {
-        version: version,
-        company: company,
-        file_type: file_type,
-        UNKNOWN1: unknown1,
-        name_length: name_length,
-        name: name,
-        difficulty: difficulty,
-        play_time: play_time,
-        appearance: Appearance {
-            hair_style: hair_style,
-            hair_dye: hair_dye,
-            hide_visual: hide_visual,
-            hide_misc: hide_misc,
-            gender: gender,
-            hair_colour: hair_colour,
-            skin_colour: skin_colour,
-            eye_colour: eye_colour,
-            shirt_colour: shirt_colour,
-            undershirt_colour: undershirt_colour,
-            pants_colour: pants_colour,
-            shoes_colour: shoes_colour },
-        stat_life: stat_life,
-        stat_life_max: stat_life_max,
-        stat_mana: stat_mana,
-        stat_mana_max: stat_mana_max,
-        extra_accessory: extra_accessory,
-        done_dundefii_event: done_dundefii_event,
-        tax_money: tax_money,
-        // appearance: Appearance{}, // The rest of the appearance
-        UNKNOWN2: unknown2,
-        armor: armor,
-        accessories: accessories,
-        UNKNOWN3: unknown3,
-        accessories_vanity: accessories_vanity,
-        UNKNOWN4: unknown4,
-        dyes: dyes,
-        UNKNOWN5: unknown5,
-        inventory1: inventory1,
-        inventory2: inventory2,
-        UNKNOWN6: data[reader.position() as usize..].to_vec(),
-        raw_length_bytes: data.len()
+
+use crate::difficulty::Difficulty;
+use crate::error::PlrError;
+use crate::layout::{
+    Feature,
+    Layout,
+};
+use crate::output::{
+    default_json_file_name,
+    default_plr_file_name,
+    write_output,
+    OverwritePolicy,
+};
+use crate::research::Research;
+use crate::schema::{
+    plr_layout,
+    BankSlots,
+    Buffs,
+    BuilderAccessories,
+    Bytes,
+    Codec,
+    Counted,
+    Flagged,
+    Rest,
+    SpawnPoints,
+};
+use crate::stream::{
+    is_bad_padding,
+    DecryptReader,
+    EncryptWriter,
+};
+
+// The company name every player file carries right after the version
+const MAGIC: &[u8; 7] = b"relogic";
+
+pub fn decrypt_plr_aes128cbc(mut data: Vec<u8>, key: &[u8]) -> Result<Vec<u8>, PlrError> {
+    let cipher = Aes128CbcDec::new_from_slices(key, key).map_err(|e| PlrError::Decryption(format!("Invalid key: {}", e)))?;
+    let raw = cipher
+        .decrypt_padded_mut::<Pkcs7>(data.as_mut_slice())
+        .map_err(|_| PlrError::WrongKey)?;
+    // A wrong key garbles everything, so the magic right after the version is a cheap sanity check
+    if raw.get(4..11) != Some(MAGIC.as_slice()) {
+        return Err(PlrError::WrongKey);
+    }
+    return Ok(raw.to_owned());
+}
+
+pub fn encrypt_plr_aes128cbc(data: Vec<u8>, key: &[u8]) -> Result<Vec<u8>, PlrError> {
+    let cipher = Aes128CbcEnc::new_from_slices(key, key).map_err(|e| PlrError::Decryption(format!("Invalid key: {}", e)))?;
+    return Ok(cipher.encrypt_padded_vec_mut::<Pkcs7>(data.as_slice()));
+}
+
+pub fn deserialize_raw_to_struct_plr(data: Vec<u8>) -> Result<Plr, PlrError> {
+    return deserialize_plr_from(data.as_slice());
+}
+
+pub fn deserialize_plr_from<R: Read>(reader: R) -> Result<Plr, PlrError> {
+    let mut reader = CountingReader::new(reader);
+    return deserialize(&mut reader);
+}
+
+// Also returns where each field starts in `data`, for pointing at the field behind a byte offset
+pub fn deserialize_plr_traced(data: &[u8]) -> Result<(Plr, Vec<FieldSpan>), PlrError> {
+    let mut reader = CountingReader { inner: data, offset: 0, spans: Some(Vec::new()), runs: 0 };
+    let plr = deserialize(&mut reader)?;
+    return Ok((plr, reader.spans.unwrap_or_default()));
+}
+
+fn deserialize<R: Read>(reader: &mut CountingReader<R>) -> Result<Plr, PlrError> {
+    reader.mark("version");
+    let version: u32 = PlrUnpacker::r_u32(reader)?;
+    reader.mark("company");
+    let magic = PlrUnpacker::r_bytes(reader, 7)?;
+    if magic != MAGIC {
+        return Err(PlrError::BadMagic { found: magic });
+    }
+    let layout = Layout::for_version(version)?;
+
+    let mut plr = Plr {
+        version,
+        company: String::from_utf8_lossy(&magic).into_owned(),
+        ..Plr::default()
     };
+    plr.read_fields(reader, &layout)?;
+    return Ok(plr);
+}
+
+pub fn serialize_struct_to_raw_plr(plr: &Plr) -> Result<Vec<u8>, PlrError> {
+    let mut data: Vec<u8> = Vec::new();
+    serialize_plr_to(plr, &mut data)?;
+    return Ok(data);
+}
+
+pub fn serialize_plr_to<W: Write>(plr: &Plr, mut writer: W) -> Result<(), PlrError> {
+    let layout = Layout::for_version(plr.version)?;
 
-    return plr;
-}
-
-pub fn serialize_struct_to_raw_plr(plr: &Plr) -> Vec<u8> {
-    let mut data: Vec<u8> = vec![0; plr.raw_length_bytes];
+    // The magic is also how a wrong key is told apart, so a player without it could never be read back
+    if plr.company.as_bytes() != MAGIC {
+        return Err(PlrError::InvalidField {
+            field: "company",
+            reason: format!("expected {:?}, found {:?}", String::from_utf8_lossy(MAGIC), plr.company),
+        });
+    }
+    PlrPacker::w_u32(&mut writer, plr.version)?;
+    PlrPacker::w_bytes(&mut writer, plr.company.as_bytes())?;
+    plr.write_fields(&mut writer, &layout)?;
+    return Ok(());
+}
+
+pub fn decode_plr(plr_file: Vec<u8>, key: &[u8]) -> Result<Plr, PlrError> {
+    let raw = decrypt_plr_aes128cbc(plr_file, key)?;
+    return deserialize_raw_to_struct_plr(raw);
+}
 
-    {
-        let mut writer = Cursor::new(&mut data);
+pub fn encode_plr(plr: &Plr, key: &[u8]) -> Result<Vec<u8>, PlrError> {
+    let raw = serialize_struct_to_raw_plr(plr)?;
+    return encrypt_plr_aes128cbc(raw, key);
+}
 
-        PlrPacker::w_u32(&mut writer, plr.version);
-        PlrPacker::w_string(&mut writer, &plr.company);
-        PlrPacker::w_u8(&mut writer, plr.file_type);
+pub fn read_plr<R: Read>(reader: R, key: &[u8]) -> Result<Plr, PlrError> {
+    return Plr::read_from(reader, key);
+}
 
-        PlrPacker::w_bytes(&mut writer, plr.UNKNOWN1.to_owned());
+pub fn write_plr<W: Write>(plr: &Plr, writer: W, key: &[u8]) -> Result<(), PlrError> {
+    return plr.write_to(writer, key);
+}
 
-        PlrPacker::w_u8(&mut writer, plr.name_length);
-        PlrPacker::w_string(&mut writer, &plr.name);
-        PlrPacker::w_i8(&mut writer, plr.difficulty);
-        PlrPacker::w_i64(&mut writer, plr.play_time);
+pub fn read_json<R: Read>(reader: R) -> Result<Plr, PlrError> {
+    return Ok(serde_json::from_reader(reader)?);
+}
 
-        PlrPacker::w_i32(&mut writer, plr.appearance.hair_style);
-        PlrPacker::w_u8(&mut writer, plr.appearance.hair_dye);
-        PlrPacker::w_u16(&mut writer, plr.appearance.hide_visual);
-        PlrPacker::w_u8(&mut writer, plr.appearance.hide_misc);
-        PlrPacker::w_u8(&mut writer, plr.appearance.gender);
+pub fn write_json<W: Write>(plr: &Plr, writer: W) -> Result<(), PlrError> {
+    serde_json::to_writer(writer, plr)?;
+    return Ok(());
+}
 
-        PlrPacker::w_i32(&mut writer, plr.stat_life);
-        PlrPacker::w_i32(&mut writer, plr.stat_life_max);
-        PlrPacker::w_i32(&mut writer, plr.stat_mana);
-        PlrPacker::w_i32(&mut writer, plr.stat_mana_max);
-        PlrPacker::w_bool(&mut writer, plr.extra_accessory);
+// Without an explicit output, writes ./DECRYPTED_<sanitised name>.json
+pub fn deconstruct_plr(plr_file: Vec<u8>, key: &[u8], output: Option<&Path>, policy: OverwritePolicy) -> Result<Plr, PlrError> {
+    let plr = decode_plr(plr_file, key)?;
+    let data = serde_json::to_string(&plr)?;
+    let default_output = PathBuf::from(".").join(default_json_file_name(&plr.name));
+    write_output(output.unwrap_or(&default_output), data.as_bytes(), policy)?;
+    return Ok(plr);
+}
 
-        PlrPacker::w_bool(&mut writer, plr.done_dundefii_event);
+// Without an explicit output, writes ./COPY_<sanitised name>.plr
+pub fn reconstruct_plr(data: Vec<u8>, key: &[u8], output: Option<&Path>, policy: OverwritePolicy) -> Result<Plr, PlrError> {
+    let plr: Plr = serde_json::from_slice(data.as_slice())?;
+    let encrypted = encode_plr(&plr, key)?;
+    let default_output = PathBuf::from(".").join(default_plr_file_name(&plr.name));
+    write_output(output.unwrap_or(&default_output), &encrypted, policy)?;
+    return Ok(plr);
+}
 
-        PlrPacker::w_i32(&mut writer, plr.tax_money);
+#[derive(Debug, Clone)]
+pub struct FieldSpan {
+    pub offset: u64,
+    pub field: &'static str,
+    pub index: Option<usize>,
+    pub element_size: Option<u64>, // Set for runs of equally sized elements, whose index is then worked out from the offset
+    pub element_path: Option<fn(u64) -> String>, // Names the part of an element an offset into it falls in
+}
 
-        PlrPacker::w_i32(&mut writer, plr.appearance.hair_colour);
-        PlrPacker::w_i32(&mut writer, plr.appearance.skin_colour);
-        PlrPacker::w_i32(&mut writer, plr.appearance.eye_colour);
-        PlrPacker::w_i32(&mut writer, plr.appearance.shirt_colour);
-        PlrPacker::w_i32(&mut writer, plr.appearance.undershirt_colour);
-        PlrPacker::w_i32(&mut writer, plr.appearance.pants_colour);
-        PlrPacker::w_i32(&mut writer, plr.appearance.shoes_colour);
-        
-        PlrPacker::w_bytes(&mut writer, plr.UNKNOWN2.to_owned());
+impl FieldSpan {
+    pub fn describe(&self, offset: u64) -> String {
+        if let Some(size) = self.element_size {
+            let within = offset - self.offset;
+            let path = self.element_path.map_or(String::new(), |path| path(within % size));
+            return format!("{}[{}]{}", self.field, within / size, path);
+        }
+        if let Some(index) = self.index {
+            return format!("{}[{}]", self.field, index);
+        }
+        return self.field.to_owned();
+    }
+}
 
-        plr.armor.map(|e| {PlrPacker::w_i32(&mut writer, e.id); PlrPacker::w_u8(&mut writer, e.prefix)});
-        plr.accessories.map(|e| {PlrPacker::w_i32(&mut writer, e.id); PlrPacker::w_u8(&mut writer, e.prefix)});
-        
-        PlrPacker::w_bytes(&mut writer, plr.UNKNOWN3.to_owned());
-        
-        plr.accessories_vanity.map(|e| {PlrPacker::w_i32(&mut writer, e.id); PlrPacker::w_u8(&mut writer, e.prefix)});
+pub(crate) struct CountingReader<R: Read> {
+    inner: R,
+    offset: u64,
+    spans: Option<Vec<FieldSpan>>,
+    runs: usize, // How many `read_run`s are in progress
+}
 
-        PlrPacker::w_bytes(&mut writer, plr.UNKNOWN4.to_owned());
+impl<R: Read> CountingReader<R> {
+    pub(crate) fn new(inner: R) -> CountingReader<R> {
+        return CountingReader { inner, offset: 0, spans: None, runs: 0 };
+    }
 
-        plr.dyes.map(|e| {PlrPacker::w_i32(&mut writer, e.id); PlrPacker::w_u8(&mut writer, e.prefix)});
+    fn push_span(&mut self, span: FieldSpan) {
+        if self.runs > 0 {
+            return;
+        }
+        if let Some(spans) = self.spans.as_mut() {
+            spans.push(span);
+        }
+    }
 
-        PlrPacker::w_bytes(&mut writer, plr.UNKNOWN5.to_owned());
+    pub(crate) fn mark(&mut self, field: &'static str) {
+        self.push_span(FieldSpan { offset: self.offset, field, index: None, element_size: None, element_path: None });
+    }
 
-        plr
-            .inventory1
-            .map(
-                |i| {
-                    PlrPacker::w_i32(&mut writer, i.id);
-                    PlrPacker::w_i32(&mut writer, i.stack);
-                    PlrPacker::w_u8(&mut writer, i.prefix);
-                    PlrPacker::w_bool(&mut writer, i.favourites);
-                }
-            )
-        ;
-        plr
-            .inventory2
-            .map(
-                |i| {
-                    PlrPacker::w_i32(&mut writer, i.id);
-                    PlrPacker::w_i32(&mut writer, i.stack);
-                    PlrPacker::w_u8(&mut writer, i.prefix);
-                    PlrPacker::w_bool(&mut writer, i.favourites);
-                }
-            )
-        ;
+    pub(crate) fn mark_indexed(&mut self, field: &'static str, index: usize) {
+        self.push_span(FieldSpan { offset: self.offset, field, index: Some(index), element_size: None, element_path: None });
+    }
 
-        PlrPacker::w_bytes(&mut writer, plr.UNKNOWN6.to_owned());
+    pub(crate) fn mark_array(&mut self, field: &'static str, element_size: u64) {
+        self.push_span(FieldSpan { offset: self.offset, field, index: None, element_size: Some(element_size), element_path: None });
     }
 
-    return data;
+    // Marks a run of fixed-width elements and reads it with `read`. The one span describes every byte of the run, so
+    // any the elements would mark themselves are left out rather than shadowing it.
+    pub(crate) fn read_run<T>(
+        &mut self,
+        field: &'static str,
+        element_size: u64,
+        element_path: fn(u64) -> String,
+        read: impl FnOnce(&mut Self) -> Result<T, PlrError>,
+    ) -> Result<T, PlrError> {
+        self.push_span(FieldSpan { offset: self.offset, field, index: None, element_size: Some(element_size), element_path: Some(element_path) });
+        self.runs += 1;
+        let result = read(self);
+        self.runs -= 1;
+        return result;
+    }
 }
 
-pub fn deconstruct_plr(plr_file: Vec<u8>, key: &[u8]) -> Plr {
-    let raw = decrypt_plr_aes128cbc(plr_file, key);
-    let plr = deserialize_raw_to_struct_plr(raw);
-    let data = serde_json::to_string(&plr).expect("Error serializing PLR to JSON");
-    write(format!("./DECRYPTED_{}.json", plr.name), data).expect("Error writing decrypted data to file");
-    return plr;
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.offset += n as u64;
+        return Ok(n);
+    }
 }
 
-pub fn reconstruct_plr(data: Vec<u8>, key: &[u8]) -> Plr {
-    let plr = serde_json::from_slice(data.as_slice()).expect("Error deserializing JSON into plr");
-    let raw = serialize_struct_to_raw_plr(&plr);
-    let encrypted = encrypt_plr_aes128cbc(raw, key);
-    write(format!("./COPY_{}.plr", &plr.name), encrypted).expect("Error serializing PLR");
-    return plr;
-}
-
-struct PlrUnpacker;
+pub(crate) struct PlrUnpacker;
 
 impl PlrUnpacker {
-    pub fn r_i8<R: Read + Seek>(reader: &mut R) -> i8 {
-        return reader.read_i8().expect("Error reading byte as int8");
+    /// Runs a primitive read, turning a short read into `PlrError::Truncated` at the offset the value started at.
+    fn at<R: Read, T>(reader: &mut CountingReader<R>, read: impl FnOnce(&mut CountingReader<R>) -> io::Result<T>) -> Result<T, PlrError> {
+        let offset = reader.offset;
+        return read(reader).map_err(|e| match e.kind() {
+            ErrorKind::UnexpectedEof => PlrError::Truncated { offset },
+            _ => PlrError::Io(e),
+        });
     }
 
-    pub fn r_u8<R: Read + Seek>(reader: &mut R) -> u8 {
-        return reader.read_u8().expect("Error reading byte as uint8");
+    pub fn r_i8<R: Read>(reader: &mut CountingReader<R>) -> Result<i8, PlrError> {
+        return PlrUnpacker::at(reader, |r| r.read_i8());
     }
 
-    pub fn r_u16<R: Read + Seek>(reader: &mut R) -> u16 {
-        return reader.read_u16::<LittleEndian>().expect("Error reading bytes as uint16");
+    pub fn r_u8<R: Read>(reader: &mut CountingReader<R>) -> Result<u8, PlrError> {
+        return PlrUnpacker::at(reader, |r| r.read_u8());
     }
 
-    pub fn r_i32<R: Read + Seek>(reader: &mut R) -> i32 {
-        return reader.read_i32::<LittleEndian>().expect("Error reading bytes as int32");
+    pub fn r_u16<R: Read>(reader: &mut CountingReader<R>) -> Result<u16, PlrError> {
+        return PlrUnpacker::at(reader, |r| r.read_u16::<LittleEndian>());
     }
 
-    pub fn r_u32<R: Read + Seek + std::fmt::Debug>(reader: &mut R) -> u32 {
-        return reader.read_u32::<LittleEndian>().expect("Error reading bytes as uint32");
+    pub fn r_i32<R: Read>(reader: &mut CountingReader<R>) -> Result<i32, PlrError> {
+        return PlrUnpacker::at(reader, |r| r.read_i32::<LittleEndian>());
     }
 
-    pub fn r_i64<R: Read + Seek>(reader: &mut R) -> i64 {
-        return reader.read_i64::<LittleEndian>().expect("Error reading bytes as int64");
+    pub fn r_u32<R: Read>(reader: &mut CountingReader<R>) -> Result<u32, PlrError> {
+        return PlrUnpacker::at(reader, |r| r.read_u32::<LittleEndian>());
     }
 
-    pub fn r_bool<R: Read + Seek>(reader: &mut R) -> bool {
-        return reader.read_u8().expect("Error reading byte as bool") != 0;
+    pub fn r_i64<R: Read>(reader: &mut CountingReader<R>) -> Result<i64, PlrError> {
+        return PlrUnpacker::at(reader, |r| r.read_i64::<LittleEndian>());
     }
 
-    pub fn r_bytes<R: Read + Seek>(reader: &mut R, n: usize) -> Vec<u8> {
-        let mut _buf = vec![0; n];
-        let buf = _buf.as_mut_slice();
-        let _n = reader.read_exact(buf).expect("Error reading bytes");
-        return buf.to_vec();
+    pub fn r_f32<R: Read>(reader: &mut CountingReader<R>) -> Result<f32, PlrError> {
+        return PlrUnpacker::at(reader, |r| r.read_f32::<LittleEndian>());
     }
 
-    pub fn r_string<R: Read + Seek>(reader: &mut R, l: usize) -> String {
-        let mut buf = vec![0; l];
-        let _n = reader.read_exact(&mut buf).expect("Error reading bytes");
-        return String::from_utf8(buf).expect("Error converting byte vector into String");
+    pub fn r_bool<R: Read>(reader: &mut CountingReader<R>) -> Result<bool, PlrError> {
+        return Ok(PlrUnpacker::at(reader, |r| r.read_u8())? != 0);
+    }
+
+    // Lengths come from the file, so the buffer only grows as bytes arrive rather than being allocated up front
+    pub fn r_bytes<R: Read>(reader: &mut CountingReader<R>, n: usize) -> Result<Vec<u8>, PlrError> {
+        let offset = reader.offset;
+        let mut buf = Vec::new();
+        reader.by_ref().take(n as u64).read_to_end(&mut buf)?;
+        if buf.len() < n {
+            return Err(PlrError::Truncated { offset });
+        }
+        return Ok(buf);
+    }
+
+    pub fn r_to_end<R: Read>(reader: &mut CountingReader<R>) -> Result<Vec<u8>, PlrError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        return Ok(buf);
+    }
+
+    // .NET BinaryReader.Read7BitEncodedInt: 7 bits per byte, low groups first, high bit set on every byte but the last
+    pub fn r_7bit_int<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<i32, PlrError> {
+        let offset = reader.offset;
+        let mut value: u32 = 0;
+        for shift in (0..35).step_by(7) {
+            let b = PlrUnpacker::r_u8(reader)?;
+            value |= ((b & 0x7f) as u32) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value as i32);
+            }
+        }
+        return Err(PlrError::InvalidField {
+            field,
+            reason: format!("7-bit encoded length at offset {} is longer than 5 bytes", offset),
+        });
+    }
+
+    // .NET BinaryReader.ReadString: 7-bit encoded byte length followed by UTF-8 bytes
+    pub fn r_string<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<String, PlrError> {
+        let l = PlrUnpacker::r_7bit_int(reader, field)?;
+        let l = usize::try_from(l).map_err(|_| PlrError::InvalidField {
+            field,
+            reason: format!("negative string length {}", l),
+        })?;
+        let offset = reader.offset;
+        let buf = PlrUnpacker::r_bytes(reader, l)?;
+        return String::from_utf8(buf).map_err(|_| PlrError::InvalidUtf8 { field, offset });
     }
 }
 
-struct PlrPacker;
+pub(crate) struct PlrPacker;
 
 impl PlrPacker {
-    pub fn w_i8<W: Write + Seek>(writer: &mut W, v: i8) -> usize {
-        writer.write_i8(v).expect("Error writing signed byte to buffer");
-        return 1;
+    pub fn w_i8<W: Write>(writer: &mut W, v: i8) -> Result<usize, PlrError> {
+        writer.write_i8(v)?;
+        return Ok(1);
     }
 
-    pub fn w_u8<W: Write + Seek>(writer: &mut W, v: u8) -> usize {
-        writer.write_u8(v).expect("Error writing unsigned byte to buffer");
-        return 1;
+    pub fn w_u8<W: Write>(writer: &mut W, v: u8) -> Result<usize, PlrError> {
+        writer.write_u8(v)?;
+        return Ok(1);
     }
 
-    pub fn w_u16<W: Write + Seek>(writer: &mut W, v: u16) -> usize {
-        writer.write_u16::<LittleEndian>(v).expect("Error writing int32 to buffer");
-        return 2;
+    pub fn w_u16<W: Write>(writer: &mut W, v: u16) -> Result<usize, PlrError> {
+        writer.write_u16::<LittleEndian>(v)?;
+        return Ok(2);
     }
 
-    pub fn w_i32<W: Write + Seek>(writer: &mut W, v: i32) -> usize {
-        writer.write_i32::<LittleEndian>(v).expect("Error writing int32 to buffer");
-        return 4;
+    pub fn w_i32<W: Write>(writer: &mut W, v: i32) -> Result<usize, PlrError> {
+        writer.write_i32::<LittleEndian>(v)?;
+        return Ok(4);
     }
 
-    pub fn w_u32<W: Write + Seek>(writer: &mut W, v: u32) -> usize {
-        writer.write_u32::<LittleEndian>(v).expect("Error writing uint32 to buffer");
-        return 4;
+    pub fn w_u32<W: Write>(writer: &mut W, v: u32) -> Result<usize, PlrError> {
+        writer.write_u32::<LittleEndian>(v)?;
+        return Ok(4);
     }
 
-    pub fn w_i64<W: Write + Seek>(writer: &mut W, v: i64) -> usize {
-        writer.write_i64::<LittleEndian>(v).expect("Error writing int64 to buffer");
-        return 8;
+    pub fn w_i64<W: Write>(writer: &mut W, v: i64) -> Result<usize, PlrError> {
+        writer.write_i64::<LittleEndian>(v)?;
+        return Ok(8);
     }
 
-    pub fn w_bool<W: Write + Seek>(writer: &mut W, v: bool) -> usize {
-        writer.write_u8(if v {1} else {0}).expect("Error writing bool as uint8 to buffer");
-        return 1;
+    pub fn w_f32<W: Write>(writer: &mut W, v: f32) -> Result<usize, PlrError> {
+        writer.write_f32::<LittleEndian>(v)?;
+        return Ok(4);
     }
 
-    pub fn w_bytes<W: Write + Seek>(writer: &mut W, v: Vec<u8>) -> usize {
-        return writer.write(&v).expect("Error writing bytes to buffer");
+    pub fn w_bool<W: Write>(writer: &mut W, v: bool) -> Result<usize, PlrError> {
+        writer.write_u8(if v {1} else {0})?;
+        return Ok(1);
     }
 
-    pub fn w_string<W: Write + Seek>(writer: &mut W, v: &str) -> usize {
-        writer.write(v.as_bytes()).expect("Error writing String to buffer");
-        return 0;
+    pub fn w_bytes<W: Write>(writer: &mut W, v: &[u8]) -> Result<usize, PlrError> {
+        writer.write_all(v)?;
+        return Ok(v.len());
+    }
+
+    pub fn w_7bit_int<W: Write>(writer: &mut W, v: u32) -> Result<usize, PlrError> {
+        let mut v = v;
+        let mut n = 0;
+        while v >= 0x80 {
+            n += PlrPacker::w_u8(writer, (v as u8) | 0x80)?;
+            v >>= 7;
+        }
+        n += PlrPacker::w_u8(writer, v as u8)?;
+        return Ok(n);
+    }
+
+    pub fn w_string<W: Write>(writer: &mut W, v: &str) -> Result<usize, PlrError> {
+        let n = PlrPacker::w_7bit_int(writer, v.len() as u32)?;
+        writer.write_all(v.as_bytes())?;
+        return Ok(n + v.len());
     }
 }
 
@@ -407,13 +441,144 @@ pub struct Appearance {
     pub hide_misc: u8,
     pub gender: u8,
 
-    pub hair_colour: i32,
-    pub skin_colour: i32,
-    pub eye_colour: i32,
-    pub shirt_colour: i32,
-    pub undershirt_colour: i32,
-    pub pants_colour: i32,
-    pub shoes_colour: i32,
+    pub hair_colour: Rgb,
+    pub skin_colour: Rgb,
+    pub eye_colour: Rgb,
+    pub shirt_colour: Rgb,
+    pub undershirt_colour: Rgb,
+    pub pants_colour: Rgb,
+    pub shoes_colour: Rgb,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct Armor<T = Equipment> {
+    pub head: T,
+    pub body: T,
+    pub legs: T,
+    pub accessories: [T; 7], // The 6th needs the Demon Heart (`extra_accessory`), the 7th Master Mode
+    pub vanity_head: T,
+    pub vanity_body: T,
+    pub vanity_legs: T,
+    pub vanity_accessories: [T; 7],
+}
+
+impl<T> Armor<T> {
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Armor<U> {
+        return Armor {
+            head: f(self.head),
+            body: f(self.body),
+            legs: f(self.legs),
+            accessories: self.accessories.map(&mut f),
+            vanity_head: f(self.vanity_head),
+            vanity_body: f(self.vanity_body),
+            vanity_legs: f(self.vanity_legs),
+            vanity_accessories: self.vanity_accessories.map(&mut f),
+        };
+    }
+
+    // In the game's slot order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        return [&self.head, &self.body, &self.legs].into_iter()
+            .chain(self.accessories.iter())
+            .chain([&self.vanity_head, &self.vanity_body, &self.vanity_legs])
+            .chain(self.vanity_accessories.iter());
+    }
+}
+
+// One dye per armor and accessory slot; vanity items show the dye of the slot they cover
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct Dyes<T = Equipment> {
+    pub head: T,
+    pub body: T,
+    pub legs: T,
+    pub accessories: [T; 7],
+}
+
+impl<T> Dyes<T> {
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Dyes<U> {
+        return Dyes {
+            head: f(self.head),
+            body: f(self.body),
+            legs: f(self.legs),
+            accessories: self.accessories.map(&mut f),
+        };
+    }
+
+    // In the game's slot order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        return [&self.head, &self.body, &self.legs].into_iter().chain(self.accessories.iter());
+    }
+}
+
+// Used for both the equipment and the dye of each slot
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct MiscEquipment {
+    pub pet: Equipment,
+    pub light_pet: Equipment,
+    pub minecart: Equipment,
+    pub mount: Equipment,
+    pub hook: Equipment,
+}
+
+// Stored as 3 raw bytes; shown in JSON as "#rrggbb"
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b);
+    }
+}
+
+impl std::str::FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("Invalid colour {:?} (expected #rrggbb)", s));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).expect("checked to be hex digits");
+        return Ok(Rgb { r: channel(0), g: channel(2), b: channel(4) });
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_str(self);
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        return s.parse().map_err(de::Error::custom);
+    }
 }
 
 #[derive(
@@ -441,7 +606,175 @@ pub struct Item {
     pub id: i32,
     pub stack: i32,
     pub prefix: u8,
-    pub favourites: bool
+    pub favourites: bool,
+    // Set on read for IDs past the last vanilla item (newer game version or modded); the slot is still written back verbatim
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unknown: bool
+}
+
+pub const MAX_ITEM_ID: i32 = 5455;
+
+pub const INVENTORY_SLOTS: usize = 58;
+
+// The 58 saved inventory slots in the game's order. The trash slot is not saved with the player, and the item
+// held on the mouse is kept in `Plr::temporary_items`.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct Inventory {
+    pub hotbar: [Item; 10], // Slots 0–9, the top row of the main grid
+    pub main: [[Item; 10]; 4], // Slots 10–49, row by row
+    pub coins: [Item; 4], // Slots 50–53
+    pub ammo: [Item; 4], // Slots 54–57
+}
+
+impl Inventory {
+    pub fn slot(&self, index: usize) -> Option<&Item> {
+        return match index {
+            0..=9 => self.hotbar.get(index),
+            10..=49 => self.main[(index - 10) / 10].get(index % 10),
+            50..=53 => self.coins.get(index - 50),
+            _ => self.ammo.get(index.checked_sub(54)?),
+        };
+    }
+
+    pub fn slot_mut(&mut self, index: usize) -> Option<&mut Item> {
+        return match index {
+            0..=9 => self.hotbar.get_mut(index),
+            10..=49 => self.main[(index - 10) / 10].get_mut(index % 10),
+            50..=53 => self.coins.get_mut(index - 50),
+            _ => self.ammo.get_mut(index.checked_sub(54)?),
+        };
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Item> {
+        return self.hotbar.iter().chain(self.main.iter().flatten()).chain(self.coins.iter()).chain(self.ammo.iter());
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        return self.hotbar.iter_mut().chain(self.main.iter_mut().flatten()).chain(self.coins.iter_mut()).chain(self.ammo.iter_mut());
+    }
+}
+
+// Items parked in UI slots when the player was saved; each is only written when occupied
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct TemporaryItems {
+    pub mouse: Option<Item>,
+    pub creative_sacrifice: Option<Item>,
+    pub guide: Option<Item>,
+    pub reforge: Option<Item>,
+}
+
+pub const LOADOUTS: usize = 3;
+
+// A saved equipment set. The one in use lives in `Plr::armor`, `Plr::dyes` and `Appearance::hide_visual`
+// instead; see `Plr::loadout`.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct Loadout {
+    pub armor: Armor<Item>,
+    pub dyes: Dyes<Item>,
+    pub hide_visual: [bool; 10], // Per armor and accessory slot, as in `Dyes`
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct SuperCart {
+    pub unlocked: bool,
+    pub enabled: bool,
+}
+
+// The creative powers saved with a Journey mode player; the rest, like the difficulty slider, belong to the
+// world. A power is None when the file has no record of it.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+)]
+pub struct CreativePowers {
+    pub godmode: Option<bool>,
+    pub far_placement_range: Option<bool>,
+    pub spawn_rate: Option<f32>, // Slider position from 0 to 1; 0.5 is the normal rate
+}
+
+// One item in `Research`, keyed by the item's internal name
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+)]
+pub struct ResearchEntry {
+    pub item: String,
+    pub count: i32,
+}
+
+impl Item {
+    pub fn is_unknown_id(id: i32) -> bool {
+        return id > MAX_ITEM_ID;
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+)]
+pub struct Buff {
+    pub buff_type: i32,
+    pub time: i32
+}
+
+impl Buff {
+    pub fn is_empty(&self) -> bool {
+        return self.buff_type == 0 && self.time == 0;
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+)]
+pub struct SpawnPoint {
+    pub x: i32,
+    pub y: i32,
+    pub world_id: i32,
+    pub world_name: String
 }
 
 #[allow(non_snake_case)]
@@ -456,9 +789,8 @@ pub struct Plr {
     pub company: String,
     pub file_type: u8,
     pub UNKNOWN1: Vec<u8>, // 12 bytes
-    pub name_length: u8,
     pub name: String,
-    pub difficulty: i8,
+    pub difficulty: Difficulty,
     pub play_time: i64,
     pub appearance: Appearance,
     pub stat_life: i32,
@@ -466,28 +798,227 @@ pub struct Plr {
     pub stat_mana: i32,
     pub stat_mana_max: i32,
     pub extra_accessory: bool,
+    pub unlocked_biome_torches: bool,
+    pub using_biome_torches: bool,
+    pub ate_artisan_bread: bool,
+    pub used_aegis_crystal: bool,
+    pub used_aegis_fruit: bool,
+    pub used_arcane_crystal: bool,
+    pub used_galaxy_pearl: bool,
+    pub used_gummy_worm: bool,
+    pub used_ambrosia: bool,
     pub done_dundefii_event: bool,
     pub tax_money: i32,
-    // pub appearance: Appearance, // The rest of the appearance
-    pub UNKNOWN2: Vec<u8>, // 10 bytes
-    pub armor: [Equipment; 3],
-    // armor: [(Equipment, Equipment, Equipment)],
-    pub accessories: [Equipment; 6],
-    // accessories: [(Equipment, Equipment, Equipment, Equipment, Equipment, Equipment)],
-    pub UNKNOWN3: Vec<u8>, // 20 bytes
-    pub accessories_vanity: [Equipment; 6],
-    // accessories_vanity: [(Equipment, Equipment, Equipment, Equipment, Equipment, Equipment)],
-    pub UNKNOWN4: Vec<u8>, // 5 bytes
-    pub dyes: [Equipment; 8],
-    // dyes: [(Equipment, Equipment, Equipment, Equipment, Equipment, Equipment, Equipment, Equipment)],
-    pub UNKNOWN5: Vec<u8>, // 10 bytes
-    pub inventory1: [Item; 32],
-    pub inventory2: [Item; 26],
-    pub UNKNOWN6: Vec<u8>, // Stuff like misc_equipments(id, prefix) misc_dyes(id, prefix) piggy_bank(id, stack, prefix) safe(id, stack, prefix) buffs(id, duration) world_list(if_-1_skip, spawn_x, spawn_y, address, name) hotbar_locked(bool) hide_statuses_under_map(bool[]) fishing_quests_completed(int32) dpa_bindings(int32) builder_account_status(int32) bartender_quests(int32) mod_data padding
-    pub raw_length_bytes: usize
+    pub deaths_pve: i32,
+    pub deaths_pvp: i32,
+    pub armor: Armor,
+    pub dyes: Dyes,
+    pub inventory: Inventory,
+    pub misc_equipment: MiscEquipment,
+    pub misc_dyes: MiscEquipment,
+    pub piggy_bank: Vec<Item>, // 40 slots
+    pub safe: Vec<Item>, // 40 slots
+    pub defenders_forge: Vec<Item>, // 40 slots
+    pub void_vault: Vec<Item>, // 40 slots
+    pub void_vault_info: u8,
+    pub buffs: Vec<Buff>, // Trailing empty slots are dropped on read and padded back on write
+    pub spawn_points: Vec<SpawnPoint>,
+    pub hotbar_locked: bool,
+    pub hide_info: [bool; 13],
+    pub angler_quests_finished: i32,
+    pub dpad_bindings: [i32; 4],
+    pub builder_accessory_status: Vec<i32>,
+    pub bartender_quest_log: i32,
+    pub respawn_timer: Option<i32>, // Set while the player is dead
+    pub last_save_time: i64, // .NET DateTime.ToBinary
+    pub golfer_score: i32,
+    pub research: Research,
+    pub temporary_items: TemporaryItems,
+    pub creative_powers: CreativePowers,
+    pub super_cart: SuperCart,
+    pub current_loadout: i32,
+    pub loadouts: [Loadout; LOADOUTS],
+    pub UNKNOWN6: Vec<u8> // Anything after the last known field
+}
+
+// The binary layout after the version and magic, in file order
+plr_layout! {
+    Plr;
+    file_type;
+    UNKNOWN1 => Bytes(12);
+    name;
+    difficulty;
+    play_time;
+    appearance.hair_style;
+    appearance.hair_dye;
+    appearance.hide_visual;
+    appearance.hide_misc;
+    appearance.gender;
+    stat_life;
+    stat_life_max;
+    stat_mana;
+    stat_mana_max;
+    extra_accessory;
+    #[since(BiomeTorches)] unlocked_biome_torches;
+    #[since(BiomeTorches)] using_biome_torches;
+    #[since(ArtisanBread)] ate_artisan_bread;
+    #[since(PermanentConsumables)] used_aegis_crystal;
+    #[since(PermanentConsumables)] used_aegis_fruit;
+    #[since(PermanentConsumables)] used_arcane_crystal;
+    #[since(PermanentConsumables)] used_galaxy_pearl;
+    #[since(PermanentConsumables)] used_gummy_worm;
+    #[since(PermanentConsumables)] used_ambrosia;
+    #[since(DD2Event)] done_dundefii_event;
+    tax_money;
+    #[since(Deaths)] deaths_pve;
+    #[since(Deaths)] deaths_pvp;
+    appearance.hair_colour;
+    appearance.skin_colour;
+    appearance.eye_colour;
+    appearance.shirt_colour;
+    appearance.undershirt_colour;
+    appearance.pants_colour;
+    appearance.shoes_colour;
+    armor.head;
+    armor.body;
+    armor.legs;
+    armor.accessories;
+    armor.vanity_head;
+    armor.vanity_body;
+    armor.vanity_legs;
+    armor.vanity_accessories;
+    dyes.head;
+    dyes.body;
+    dyes.legs;
+    dyes.accessories;
+    inventory.hotbar;
+    inventory.main;
+    inventory.coins;
+    inventory.ammo;
+    misc_equipment.pet;
+    misc_dyes.pet;
+    misc_equipment.light_pet;
+    misc_dyes.light_pet;
+    misc_equipment.minecart;
+    misc_dyes.minecart;
+    misc_equipment.mount;
+    misc_dyes.mount;
+    misc_equipment.hook;
+    misc_dyes.hook;
+    piggy_bank => BankSlots { favourites: None };
+    safe => BankSlots { favourites: None };
+    #[since(DefendersForge)] defenders_forge => BankSlots { favourites: None };
+    #[since(VoidVault)] void_vault => BankSlots { favourites: Some(Feature::VoidVaultFavourites) };
+    #[since(VoidVaultInfo)] void_vault_info;
+    buffs => Buffs;
+    spawn_points => SpawnPoints;
+    hotbar_locked;
+    hide_info;
+    angler_quests_finished;
+    #[since(DpadBindings)] dpad_bindings;
+    builder_accessory_status => BuilderAccessories;
+    #[since(BartenderQuestLog)] bartender_quest_log;
+    #[since(Dead)] respawn_timer => Flagged;
+    #[since(LastSaveTime)] last_save_time;
+    #[since(GolferScore)] golfer_score;
+    #[since(Research)] research => Counted;
+    #[since(TemporaryItems)] temporary_items;
+    #[since(CreativePowers)] creative_powers;
+    #[since(SuperCart)] super_cart;
+    #[since(Loadouts)] current_loadout;
+    #[since(Loadouts)] loadouts;
+    UNKNOWN6 => Rest;
 }
 
 impl Plr {
+    pub fn held_item(&self) -> Option<&Item> {
+        return self.temporary_items.mouse.as_ref();
+    }
+
+    fn check_loadout_index(&self, index: usize) -> Result<(), PlrError> {
+        if !Layout::for_version(self.version)?.has(Feature::Loadouts) {
+            return Err(PlrError::InvalidField {
+                field: "loadouts",
+                reason: format!("version {} players have no loadouts", self.version),
+            });
+        }
+        if index >= LOADOUTS {
+            return Err(PlrError::InvalidField {
+                field: "loadouts",
+                reason: format!("no loadout {} (expected 0 to {})", index, LOADOUTS - 1),
+            });
+        }
+        return Ok(());
+    }
+
+    fn is_current_loadout(&self, index: usize) -> bool {
+        return usize::try_from(self.current_loadout) == Ok(index);
+    }
+
+    // The equipment set in loadout `index`, read from the equipped slots if it is the one in use
+    pub fn loadout(&self, index: usize) -> Result<Loadout, PlrError> {
+        self.check_loadout_index(index)?;
+        if !self.is_current_loadout(index) {
+            return Ok(self.loadouts[index]);
+        }
+        let equipped = |e: Equipment| Item {
+            id: e.id,
+            stack: if e.id == 0 {0} else {1},
+            prefix: e.prefix,
+            favourites: false,
+            unknown: Item::is_unknown_id(e.id),
+        };
+        let mut hide_visual = [false; 10];
+        for (bit, hidden) in hide_visual.iter_mut().enumerate() {
+            *hidden = self.appearance.hide_visual & (1 << bit) != 0;
+        }
+        return Ok(Loadout {
+            armor: self.armor.map(equipped),
+            dyes: self.dyes.map(equipped),
+            hide_visual,
+        });
+    }
+
+    // Replaces loadout `index`, writing to the equipped slots if it is the one in use
+    pub fn set_loadout(&mut self, index: usize, loadout: Loadout) -> Result<(), PlrError> {
+        self.check_loadout_index(index)?;
+        if !self.is_current_loadout(index) {
+            self.loadouts[index] = loadout;
+            return Ok(());
+        }
+        let equipped = |i: Item| Equipment { id: i.id, prefix: i.prefix };
+        self.armor = loadout.armor.map(equipped);
+        self.dyes = loadout.dyes.map(equipped);
+        let bits = loadout.hide_visual.iter().enumerate().fold(0u16, |bits, (bit, hidden)| if *hidden {bits | 1 << bit} else {bits});
+        self.appearance.hide_visual = (self.appearance.hide_visual & !0x3ff) | bits;
+        return Ok(());
+    }
+
+    pub fn swap_loadouts(&mut self, a: usize, b: usize) -> Result<(), PlrError> {
+        let loadout_a = self.loadout(a)?;
+        let loadout_b = self.loadout(b)?;
+        self.set_loadout(a, loadout_b)?;
+        self.set_loadout(b, loadout_a)?;
+        return Ok(());
+    }
+
+    pub fn read_from<R: Read>(reader: R, key: &[u8]) -> Result<Plr, PlrError> {
+        let reader = DecryptReader::new(reader, key)?;
+        // Through the cipher, garbage magic or padding means the key is wrong or the input was never encrypted
+        return match deserialize_plr_from(reader) {
+            Err(PlrError::BadMagic { .. }) => Err(PlrError::WrongKey),
+            Err(PlrError::Io(e)) if is_bad_padding(&e) => Err(PlrError::WrongKey),
+            result => result,
+        };
+    }
+
+    pub fn write_to<W: Write>(&self, writer: W, key: &[u8]) -> Result<(), PlrError> {
+        let mut writer = EncryptWriter::new(writer, key)?;
+        serialize_plr_to(self, &mut writer)?;
+        writer.finish()?;
+        return Ok(());
+    }
+
     #[allow(unused)]
     fn print_plr(plr: &Plr) {
         println!(
@@ -496,7 +1027,6 @@ impl Plr {
             company:\t\t{}
             file_type:\t\t{}
             UNKNOWN DATA
-            name_length:\t{}
             name:\t\t{}
             difficulty:\t\t{}
             play_time:\t\t{}
@@ -508,13 +1038,10 @@ impl Plr {
             extra_accessory:\t{}
             done_dundefii_event:\t{}
             tax_money:\t\t{}
-            OTHER HALF OF APPEARANCE
-            UNKNOWN DATA
             ",
             plr.version,
             plr.company,
             plr.file_type,
-            plr.name_length,
             plr.name,
             plr.difficulty,
             plr.play_time,
@@ -527,12 +1054,305 @@ impl Plr {
             plr.done_dundefii_event,
             plr.tax_money,
         );
-        plr.armor.map(|e| println!("armor:\t{:?}", e));
-        plr.accessories.map(|e| println!("accessories:\t{:?}", e));
-        plr.accessories_vanity.map(|e| println!("accessories_vanity:\t{:?}", e));
-        plr.dyes.map(|e| println!("dyes:\t{:?}", e));
-        plr.inventory1.map(|i| println!("{:?}", i));
-        plr.inventory2.map(|i| println!("{:?}", i));
-        println!("UNKNOWN DATA\nNumber of bytes:{}", plr.raw_length_bytes);
+        println!("armor:\t{:?}", plr.armor);
+        println!("dyes:\t{:?}", plr.dyes);
+        println!("misc_equipment:\t{:?}", plr.misc_equipment);
+        println!("misc_dyes:\t{:?}", plr.misc_dyes);
+        plr.inventory.iter().for_each(|i| println!("{:?}", i));
+        println!("UNKNOWN DATA\nNumber of bytes:{}", plr.UNKNOWN6.len());
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::key::{
+        encode_key,
+        DEFAULT_KEY,
+    };
+    use crate::layout::{
+        MAX_SUPPORTED_VERSION,
+        RELEASES,
+    };
+    use crate::storage::BANK_SLOTS;
+
+    fn item(id: i32) -> Item {
+        return Item { id, stack: id % 9 + 1, prefix: (id % 80) as u8, favourites: false, unknown: false };
+    }
+
+    fn bank(first: i32, favourites: bool) -> Vec<Item> {
+        return (0..BANK_SLOTS as i32).map(|i| Item { favourites: favourites && i % 3 == 0, ..item(first + i) }).collect();
+    }
+
+    // A player with every field the version saves set to something other than its default
+    pub(crate) fn sample(version: u32) -> Plr {
+        let layout = Layout::for_version(version).unwrap();
+        let has = |feature| layout.has(feature);
+        let mut plr = Plr {
+            version,
+            company: String::from("relogic"),
+            file_type: 3,
+            UNKNOWN1: (1..=12).collect(),
+            name: String::from("Zoë"),
+            difficulty: if has(Feature::CreativePowers) {Difficulty::Journey} else {Difficulty::Hardcore},
+            play_time: 123_456_789,
+            stat_life: 400,
+            stat_life_max: 500,
+            stat_mana: 120,
+            stat_mana_max: 200,
+            extra_accessory: true,
+            tax_money: 77,
+            piggy_bank: bank(100, false),
+            safe: bank(200, false),
+            void_vault_info: if has(Feature::VoidVaultInfo) {1} else {0},
+            buffs: vec![Buff { buff_type: 1, time: 3600 }, Buff::default(), Buff { buff_type: 2, time: 60 }],
+            spawn_points: vec![SpawnPoint { x: 10, y: 20, world_id: 30, world_name: String::from("Forest") }],
+            hotbar_locked: true,
+            angler_quests_finished: 12,
+            builder_accessory_status: (1..=layout.builder_accessory_slots() as i32).collect(),
+            UNKNOWN6: vec![0xde, 0xad],
+            ..Plr::default()
+        };
+        plr.appearance.hair_style = 7;
+        plr.appearance.hide_visual = 0x0102;
+        plr.appearance.eye_colour = Rgb { r: 1, g: 2, b: 3 };
+        plr.armor.head = Equipment { id: 1, prefix: 2 };
+        plr.armor.vanity_accessories[6] = Equipment { id: 3, prefix: 4 };
+        plr.dyes.accessories[0] = Equipment { id: 5, prefix: 0 };
+        plr.misc_equipment.hook = Equipment { id: 6, prefix: 0 };
+        plr.misc_dyes.pet = Equipment { id: 7, prefix: 0 };
+        for (i, slot) in plr.inventory.iter_mut().enumerate() {
+            *slot = Item { favourites: i % 2 == 0, ..item(i as i32 + 1) };
+        }
+        plr.hide_info[4] = true;
+        if has(Feature::BiomeTorches) {
+            plr.unlocked_biome_torches = true;
+            plr.using_biome_torches = true;
+        }
+        if has(Feature::ArtisanBread) {
+            plr.ate_artisan_bread = true;
+        }
+        if has(Feature::PermanentConsumables) {
+            plr.used_aegis_crystal = true;
+            plr.used_galaxy_pearl = true;
+            plr.used_ambrosia = true;
+        }
+        if has(Feature::DD2Event) {
+            plr.done_dundefii_event = true;
+        }
+        if has(Feature::Deaths) {
+            plr.deaths_pve = 5;
+            plr.deaths_pvp = 2;
+        }
+        if has(Feature::DefendersForge) {
+            plr.defenders_forge = bank(300, false);
+        }
+        if has(Feature::VoidVault) {
+            plr.void_vault = bank(400, has(Feature::VoidVaultFavourites));
+        }
+        if has(Feature::DpadBindings) {
+            plr.dpad_bindings = [1, 2, 3, 4];
+        }
+        if has(Feature::BartenderQuestLog) {
+            plr.bartender_quest_log = 9;
+        }
+        if has(Feature::Dead) {
+            plr.respawn_timer = Some(600);
+        }
+        if has(Feature::LastSaveTime) {
+            plr.last_save_time = 0x0123_4567_89ab_cdef;
+        }
+        if has(Feature::GolferScore) {
+            plr.golfer_score = 42;
+        }
+        if has(Feature::Research) {
+            plr.research.entries = vec![
+                ResearchEntry { item: String::from("DirtBlock"), count: 100 },
+                ResearchEntry { item: String::from("Wood"), count: 37 },
+            ];
+        }
+        if has(Feature::TemporaryItems) {
+            plr.temporary_items.mouse = Some(item(8));
+            plr.temporary_items.reforge = Some(item(9));
+        }
+        if has(Feature::CreativePowers) {
+            plr.creative_powers = CreativePowers { godmode: Some(true), far_placement_range: None, spawn_rate: Some(0.25) };
+        }
+        if has(Feature::SuperCart) {
+            plr.super_cart = SuperCart { unlocked: true, enabled: false };
+        }
+        if has(Feature::Loadouts) {
+            plr.current_loadout = 1;
+            for (i, loadout) in plr.loadouts.iter_mut().enumerate() {
+                loadout.armor.body = item(10 + i as i32);
+                loadout.dyes.legs = item(20 + i as i32);
+                loadout.hide_visual[i] = true;
+            }
+        }
+        return plr;
+    }
+
+    fn string_round_trip(s: &str) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        assert_eq!(PlrPacker::w_string(&mut data, s).unwrap(), data.len());
+        let mut reader = CountingReader::new(data.as_slice());
+        assert_eq!(PlrUnpacker::r_string(&mut reader, "name").unwrap(), s);
+        assert!(PlrUnpacker::r_to_end(&mut reader).unwrap().is_empty());
+        return data;
+    }
+
+    #[test]
+    fn string_lengths_use_7_bit_groups() {
+        for (len, prefix) in [
+            (0, vec![0x00]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x01]),
+            (300, vec![0xac, 0x02]),
+            (16383, vec![0xff, 0x7f]),
+            (16384, vec![0x80, 0x80, 0x01]),
+        ] {
+            let data = string_round_trip(&"a".repeat(len));
+            assert_eq!(data[..prefix.len()], prefix, "length {}", len);
+            assert_eq!(data.len(), prefix.len() + len, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn string_lengths_count_utf8_bytes() {
+        let data = string_round_trip("Zoë");
+        assert_eq!(data, [4, b'Z', b'o', 0xc3, 0xab]);
+        // 70 characters, 140 bytes: only the byte count needs the second length byte
+        let data = string_round_trip(&"é".repeat(70));
+        assert_eq!(data[..2], [0x8c, 0x01]);
+        string_round_trip("Ærø 🐉 名前");
+    }
+
+    #[test]
+    fn bad_strings_are_rejected() {
+        let data = [3, b'a', 0xff, b'b'];
+        let mut reader = CountingReader::new(data.as_slice());
+        assert!(matches!(PlrUnpacker::r_string(&mut reader, "name"), Err(PlrError::InvalidUtf8 { field: "name", offset: 1 })));
+        let data = [0xff; 6];
+        let mut reader = CountingReader::new(data.as_slice());
+        assert!(matches!(PlrUnpacker::r_string(&mut reader, "name"), Err(PlrError::InvalidField { field: "name", .. })));
+    }
+
+    #[test]
+    fn string_lengths_past_the_data_are_truncated() {
+        // i32::MAX bytes claimed, three present: nothing that size is allocated
+        let data = [0xff, 0xff, 0xff, 0xff, 0x07, b'a', b'b', b'c'];
+        let mut reader = CountingReader::new(data.as_slice());
+        assert!(matches!(PlrUnpacker::r_string(&mut reader, "name"), Err(PlrError::Truncated { offset: 5 })));
+        let data = [4, b'a', b'b'];
+        let mut reader = CountingReader::new(data.as_slice());
+        assert!(matches!(PlrUnpacker::r_string(&mut reader, "name"), Err(PlrError::Truncated { offset: 1 })));
+    }
+
+    #[test]
+    fn unknown_item_ids_survive_json_and_are_flagged() {
+        let key = encode_key(DEFAULT_KEY);
+        let mut plr = sample(MAX_SUPPORTED_VERSION);
+        plr.inventory.hotbar[2] = Item { favourites: true, ..item(6000) };
+        plr.piggy_bank[5] = item(MAX_ITEM_ID + 1);
+        let mut original: Vec<u8> = Vec::new();
+        write_plr(&plr, &mut original, &key).unwrap();
+
+        let decoded = read_plr(original.as_slice(), &key).unwrap();
+        assert!(decoded.inventory.hotbar[2].unknown);
+        assert!(decoded.piggy_bank[5].unknown);
+        assert!(!decoded.inventory.hotbar[1].unknown);
+        assert!(!decoded.piggy_bank[4].unknown);
+
+        let mut json: Vec<u8> = Vec::new();
+        write_json(&decoded, &mut json).unwrap();
+        let from_json = read_json(json.as_slice()).unwrap();
+        assert_eq!(from_json.inventory.hotbar[2].id, 6000);
+        assert!(from_json.inventory.hotbar[2].unknown);
+        let mut rewritten: Vec<u8> = Vec::new();
+        write_plr(&from_json, &mut rewritten, &key).unwrap();
+        assert_eq!(rewritten, original);
+    }
+
+    #[test]
+    fn current_loadout_is_read_from_the_equipped_slots() {
+        let mut plr = sample(MAX_SUPPORTED_VERSION);
+        plr.current_loadout = 1;
+        // Bits 0 and 9 are slots, bit 10 is past the loadout's ten and must be left alone
+        plr.appearance.hide_visual = 1 | 1 << 9 | 1 << 10;
+        let loadout = plr.loadout(1).unwrap();
+        assert_eq!((loadout.armor.head.id, loadout.armor.head.prefix, loadout.armor.head.stack), (1, 2, 1));
+        assert_eq!((loadout.armor.body.id, loadout.armor.body.stack), (0, 0));
+        assert_eq!(loadout.dyes.accessories[0].stack, 1);
+        assert_eq!(loadout.hide_visual, [true, false, false, false, false, false, false, false, false, true]);
+        assert_eq!(plr.loadout(0).unwrap().armor.body.id, plr.loadouts[0].armor.body.id);
+        assert!(plr.loadout(3).is_err());
+    }
+
+    #[test]
+    fn setting_the_current_loadout_writes_the_equipped_slots() {
+        let mut plr = sample(MAX_SUPPORTED_VERSION);
+        plr.current_loadout = 2;
+        plr.appearance.hide_visual = 0x3ff | 1 << 10;
+        let mut loadout = plr.loadouts[0];
+        loadout.armor.legs = Item { id: 90, stack: 7, prefix: 3, favourites: false, unknown: false };
+        loadout.hide_visual = [false; 10];
+        loadout.hide_visual[2] = true;
+        plr.set_loadout(2, loadout).unwrap();
+        assert_eq!((plr.armor.legs.id, plr.armor.legs.prefix), (90, 3));
+        assert_eq!(plr.armor.body.id, loadout.armor.body.id);
+        assert_eq!(plr.appearance.hide_visual, 1 << 2 | 1 << 10);
+        // The stored copy of the loadout in use is not touched
+        assert_eq!(plr.loadouts[2].armor.body.id, 12);
+    }
+
+    #[test]
+    fn swapping_with_the_current_loadout_moves_it_through_the_equipped_slots() {
+        let mut plr = sample(MAX_SUPPORTED_VERSION);
+        plr.current_loadout = 1;
+        plr.appearance.hide_visual = 1 << 3;
+        let stored = plr.loadouts[0];
+        let equipped = plr.loadout(1).unwrap();
+        plr.swap_loadouts(0, 1).unwrap();
+        assert_eq!(plr.armor.body.id, stored.armor.body.id);
+        assert_eq!(plr.dyes.legs.id, stored.dyes.legs.id);
+        assert_eq!(plr.appearance.hide_visual, 1);
+        assert_eq!(plr.loadouts[0].armor.head.id, equipped.armor.head.id);
+        assert_eq!(plr.loadouts[0].armor.head.stack, 1);
+        assert_eq!(plr.loadouts[0].hide_visual, equipped.hide_visual);
+        assert_eq!(plr.current_loadout, 1);
+    }
+
+    #[test]
+    fn company_other_than_the_magic_is_rejected() {
+        for company in ["relogicX", "Relogic", ""] {
+            let plr = Plr { company: String::from(company), ..sample(MAX_SUPPORTED_VERSION) };
+            assert!(matches!(serialize_struct_to_raw_plr(&plr), Err(PlrError::InvalidField { field: "company", .. })), "{:?}", company);
+        }
+    }
+
+    #[test]
+    fn every_release_round_trips_with_matching_spans() {
+        let versions = RELEASES
+            .iter()
+            .flat_map(|r| std::iter::once(r.version).chain(r.adds.iter().map(|(version, _)| *version)))
+            .chain(std::iter::once(MAX_SUPPORTED_VERSION));
+        for version in versions {
+            let plr = sample(version);
+            let data = serialize_struct_to_raw_plr(&plr).unwrap();
+            let (decoded, spans) = deserialize_plr_traced(&data).unwrap();
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&plr).unwrap(), "version {}", version);
+            assert_eq!(serialize_struct_to_raw_plr(&decoded).unwrap(), data, "version {}", version);
+
+            let mut written = data[..11].to_vec();
+            let marks = plr.write_fields_traced(&mut written, &Layout::for_version(version).unwrap()).unwrap();
+            assert_eq!(written, data, "version {}", version);
+            for mark in marks.iter() {
+                let span = spans
+                    .iter()
+                    .find(|s| s.field == mark.field && s.index == mark.index)
+                    .unwrap_or_else(|| panic!("version {}: no span for {}", version, mark.field));
+                assert_eq!(span.offset, mark.offset, "version {}: {}", version, mark.field);
+            }
+        }
     }
 }
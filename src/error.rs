@@ -0,0 +1,69 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::layout::{
+    MAX_SUPPORTED_VERSION,
+    RELEASES,
+};
+
+#[derive(Debug)]
+pub enum PlrError {
+    Io(io::Error),
+    Decryption(String),
+    WrongKey,
+    BadMagic { found: Vec<u8> },
+    Truncated { offset: u64 },
+    UnsupportedVersion { version: u32 },
+    InvalidUtf8 { field: &'static str, offset: u64 },
+    InvalidField { field: &'static str, reason: String },
+    Json(serde_json::Error),
+    OutputExists { path: PathBuf },
+}
+
+impl fmt::Display for PlrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlrError::Io(e) => write!(f, "I/O error: {}", e),
+            PlrError::Decryption(msg) => write!(f, "Decryption error: {}", msg),
+            PlrError::WrongKey => write!(f, "Wrong key or not an encrypted .plr file"),
+            PlrError::BadMagic { found } => write!(f, "Bad magic: expected \"relogic\", found {:?}", String::from_utf8_lossy(found)),
+            PlrError::Truncated { offset } => write!(f, "Data truncated at offset {}", offset),
+            PlrError::UnsupportedVersion { version } => write!(
+                f,
+                "Unsupported file version {} (supported: {} from Terraria {} up to {} from Terraria {})",
+                version,
+                RELEASES[0].version,
+                RELEASES[0].name,
+                MAX_SUPPORTED_VERSION,
+                RELEASES[RELEASES.len() - 1].name,
+            ),
+            PlrError::InvalidUtf8 { field, offset } => write!(f, "Invalid UTF-8 in field {} at offset {}", field, offset),
+            PlrError::InvalidField { field, reason } => write!(f, "Invalid value for field {}: {}", field, reason),
+            PlrError::Json(e) => write!(f, "JSON error: {}", e),
+            PlrError::OutputExists { path } => write!(f, "{} already exists (use --overwrite force or backup)", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for PlrError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PlrError::Io(e) => Some(e),
+            PlrError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PlrError {
+    fn from(e: io::Error) -> Self {
+        return PlrError::Io(e);
+    }
+}
+
+impl From<serde_json::Error> for PlrError {
+    fn from(e: serde_json::Error) -> Self {
+        return PlrError::Json(e);
+    }
+}
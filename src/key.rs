@@ -0,0 +1,61 @@
+use std::env;
+use std::fs::read;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::error::PlrError;
+
+// Every vanilla player file is encrypted with this key, used as both the AES key and the IV once UTF-16LE encoded
+pub const DEFAULT_KEY: &str = "h3y_gUyZ";
+
+pub const KEY_ENV_VAR: &str = "key";
+pub const CONFIG_ENV_VAR: &str = "PLREDITOR_CONFIG";
+
+#[derive(Deserialize)]
+struct Config {
+    key: Option<String>,
+}
+
+pub fn encode_key(key: &str) -> Vec<u8> {
+    return key.encode_utf16().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+}
+
+// $PLREDITOR_CONFIG, else $XDG_CONFIG_HOME/plreditor/config.json, else ~/.config/plreditor/config.json
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_ENV_VAR) {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    return Some(config_dir.join("plreditor").join("config.json"));
+}
+
+fn key_from_config() -> Result<Option<String>, PlrError> {
+    let Some(path) = config_path() else {
+        return Ok(None);
+    };
+    let data = match read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(PlrError::Io(e)),
+    };
+    let config: Config = serde_json::from_slice(&data)?;
+    return Ok(config.key);
+}
+
+// Precedence: explicit override (CLI flag), then the `key` environment variable (which .env may set), then the config file, then DEFAULT_KEY
+pub fn resolve_key(cli_key: Option<&str>) -> Result<Vec<u8>, PlrError> {
+    if let Some(key) = cli_key {
+        return Ok(encode_key(key));
+    }
+    if let Ok(key) = env::var(KEY_ENV_VAR) {
+        return Ok(encode_key(&key));
+    }
+    if let Some(key) = key_from_config()? {
+        return Ok(encode_key(&key));
+    }
+    return Ok(encode_key(DEFAULT_KEY));
+}
@@ -0,0 +1,116 @@
+use crate::error::PlrError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    DpadBindings,
+    BuilderAccessories8,
+    BuilderAccessories10,
+    BartenderQuestLog,
+    DefendersForge,
+    DD2Event,
+    BuilderAccessories11,
+    VoidVault,
+    VoidVaultInfo,
+    Dead,
+    LastSaveTime,
+    GolferScore,
+    TemporaryItems,
+    Research,
+    CreativePowers,
+    BiomeTorches,
+    BuilderAccessories12,
+    Buffs44,
+    SuperCart,
+    Deaths,
+    VoidVaultFavourites,
+    ArtisanBread,
+    PermanentConsumables,
+    Loadouts,
+}
+
+#[derive(Debug)]
+pub struct Release {
+    pub name: &'static str,
+    pub version: u32,
+    pub adds: &'static [(u32, Feature)], // (first file version carrying the field, field), sorted by version
+}
+
+// Oldest first. A release covers every file version from its own up to the next release's, so a field first saved
+// by a development build between two releases is listed under the earlier one.
+pub const RELEASES: &[Release] = &[
+    Release { name: "1.3.0", version: 146, adds: &[(162, Feature::DpadBindings), (164, Feature::BuilderAccessories8), (167, Feature::BuilderAccessories10)] },
+    Release { name: "1.3.1", version: 169, adds: &[] },
+    Release { name: "1.3.2", version: 173, adds: &[] },
+    Release { name: "1.3.3", version: 177, adds: &[(181, Feature::BartenderQuestLog), (182, Feature::DefendersForge), (182, Feature::DD2Event)] },
+    Release { name: "1.3.4", version: 185, adds: &[] },
+    Release { name: "1.3.5", version: 193, adds: &[(197, Feature::BuilderAccessories11), (198, Feature::VoidVault), (199, Feature::VoidVaultInfo), (200, Feature::Dead), (202, Feature::LastSaveTime), (206, Feature::GolferScore), (214, Feature::TemporaryItems), (218, Feature::Research), (220, Feature::CreativePowers)] },
+    Release { name: "1.4.0", version: 225, adds: &[(229, Feature::BiomeTorches), (230, Feature::BuilderAccessories12)] },
+    Release { name: "1.4.1", version: 232, adds: &[] },
+    Release { name: "1.4.2", version: 236, adds: &[] },
+    Release { name: "1.4.3", version: 242, adds: &[(252, Feature::Buffs44), (253, Feature::SuperCart), (254, Feature::Deaths), (255, Feature::VoidVaultFavourites), (256, Feature::ArtisanBread), (260, Feature::PermanentConsumables), (262, Feature::Loadouts)] },
+    Release { name: "1.4.4", version: 269, adds: &[] },
+];
+
+pub const MAX_SUPPORTED_VERSION: u32 = 279;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    pub version: u32,
+}
+
+impl Layout {
+    pub fn for_version(version: u32) -> Result<Layout, PlrError> {
+        if version > MAX_SUPPORTED_VERSION {
+            return Err(PlrError::UnsupportedVersion { version });
+        }
+        if version < RELEASES[0].version {
+            return Err(PlrError::UnsupportedVersion { version });
+        }
+        return Ok(Layout { version });
+    }
+
+    pub fn release(&self) -> &'static Release {
+        return RELEASES.iter().rev().find(|r| r.version <= self.version).unwrap_or(&RELEASES[0]);
+    }
+
+    pub fn has(&self, feature: Feature) -> bool {
+        return RELEASES
+            .iter()
+            .flat_map(|r| r.adds.iter())
+            .any(|(version, f)| *f == feature && *version <= self.version);
+    }
+
+    pub fn buff_slots(&self) -> usize {
+        return if self.has(Feature::Buffs44) {44} else {22};
+    }
+
+    pub fn builder_accessory_slots(&self) -> usize {
+        if self.has(Feature::BuilderAccessories12) {
+            return 12;
+        } else if self.has(Feature::BuilderAccessories11) {
+            return 11;
+        } else if self.has(Feature::BuilderAccessories10) {
+            return 10;
+        } else if self.has(Feature::BuilderAccessories8) {
+            return 8;
+        }
+        return 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn releases_list_their_own_features_in_order() {
+        for (i, release) in RELEASES.iter().enumerate() {
+            let end = RELEASES.get(i + 1).map_or(u32::MAX, |next| next.version);
+            assert!(release.version < end, "{} is out of order", release.name);
+            for (version, feature) in release.adds.iter() {
+                assert!((release.version..end).contains(version), "{:?} ({}) is not in {}", feature, version, release.name);
+            }
+            assert!(release.adds.windows(2).all(|w| w[0].0 <= w[1].0), "{} adds are not sorted", release.name);
+        }
+    }
+}
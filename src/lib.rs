@@ -0,0 +1,83 @@
+//! Reading, editing and writing Terraria `.plr` player files.
+//!
+//! [`Plr::read_from`]/[`Plr::write_to`] stream encrypted player files through any `Read`/`Write`,
+//! and [`read_json`]/[`write_json`] handle the editable JSON form of [`Plr`].
+
+#![allow(clippy::needless_return)]
+
+pub mod buffs;
+pub mod difficulty;
+pub mod edit_plr;
+pub mod error;
+pub mod key;
+pub mod layout;
+pub mod output;
+pub mod powers;
+pub mod research;
+mod schema;
+pub mod spawn;
+pub mod storage;
+pub mod stream;
+pub mod upgrades;
+pub mod verify;
+
+pub use edit_plr::{
+    decode_plr,
+    decrypt_plr_aes128cbc,
+    deserialize_plr_from,
+    deserialize_plr_traced,
+    deserialize_raw_to_struct_plr,
+    encode_plr,
+    encrypt_plr_aes128cbc,
+    read_json,
+    read_plr,
+    serialize_plr_to,
+    serialize_struct_to_raw_plr,
+    write_json,
+    write_plr,
+    Appearance,
+    Armor,
+    Buff,
+    CreativePowers,
+    Dyes,
+    Equipment,
+    FieldSpan,
+    Inventory,
+    Item,
+    Loadout,
+    MiscEquipment,
+    Plr,
+    ResearchEntry,
+    Rgb,
+    SpawnPoint,
+    SuperCart,
+    TemporaryItems,
+};
+pub use error::PlrError;
+pub use key::{
+    encode_key,
+    resolve_key,
+    DEFAULT_KEY,
+};
+pub use difficulty::Difficulty;
+pub use layout::Layout;
+pub use output::OverwritePolicy;
+pub use powers::{
+    Power,
+    PowerSetting,
+};
+pub use research::Research;
+pub use storage::{
+    Bank,
+    Container,
+    Location,
+};
+pub use upgrades::{
+    PermanentUpgrades,
+    Upgrade,
+};
+pub use verify::{
+    verify_plr,
+    Mismatch,
+    VerifyReport,
+};
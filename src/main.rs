@@ -1,24 +1,671 @@
-use std::env;
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
 use std::ffi::OsStr;
-use std::fs::read;
-use std::path::Path;
+use std::fs::{
+    read,
+    File,
+};
+use std::io::{
+    stdin,
+    BufReader,
+    Read,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::process::ExitCode;
 
+use clap::{
+    Parser,
+    Subcommand,
+};
 use dotenvy::dotenv;
+use serde_json::Value;
+
+use plreditor::buffs::{
+    format_duration,
+    parse_duration,
+};
+use plreditor::edit_plr;
+use plreditor::output::write_output;
+use plreditor::research::{
+    missing_research,
+    read_catalogue,
+    CatalogueItem,
+};
+use plreditor::spawn::World;
+use plreditor::{
+    Bank,
+    Container,
+    Difficulty,
+    Item,
+    Layout,
+    Location,
+    OverwritePolicy,
+    Plr,
+    Power,
+    PowerSetting,
+    Upgrade,
+};
+
+#[derive(Parser)]
+#[command(name = "plreditor", version, about = "Terraria .plr player file editor")]
+struct Cli {
+    /// Encryption key; defaults to $key (also read from .env), then the config file, then the vanilla key
+    #[arg(long, global = true)]
+    key: Option<String>,
+
+    /// What to do when an output file already exists: refuse, force or backup (moves it to <output>.bak)
+    #[arg(long, global = true, default_value_t = OverwritePolicy::Refuse)]
+    overwrite: OverwritePolicy,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decrypt a .plr file into editable JSON
+    Decrypt {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Defaults to ./DECRYPTED_<name>.json with the name made safe for use as a file name
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Encrypt edited JSON back into a .plr file
+    Encrypt {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Defaults to ./COPY_<name>.plr with the name made safe for use as a file name
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print a .plr or JSON player as pretty JSON
+    Dump {
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Print the value at a dotted path, e.g. appearance.hair_style or inventory.main.0.0.stack (row, then slot)
+    Get {
+        #[arg(short, long)]
+        input: PathBuf,
+        path: String,
+    },
+    /// Set the value at a dotted path and write the result as .plr or JSON depending on the output extension
+    Set {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        path: String,
+        /// A JSON literal; anything that does not parse as JSON is taken as a string
+        value: String,
+    },
+    /// Check that a .plr file survives decrypt, parse, serialize and encrypt byte for byte
+    Verify {
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Print a short summary of a player
+    Info {
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Change the difficulty, adding or removing the research and creative powers that come with Journey mode
+    ConvertMode {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// classic, mediumcore, hardcore or journey
+        difficulty: Difficulty,
+    },
+    /// Copy or swap equipment loadouts (numbered 0 to 2), within a player or from another one
+    Loadout {
+        #[command(subcommand)]
+        action: LoadoutAction,
+    },
+    /// List, add, remove or move items in the piggy bank, safe, Defender's Forge and Void Vault
+    Bank {
+        #[command(subcommand)]
+        action: BankAction,
+    },
+    /// List, add, remove or extend active buffs; durations are like 90s, 5m, 1h30m or 600t (ticks)
+    Buff {
+        #[command(subcommand)]
+        action: BuffAction,
+    },
+    /// List bed spawn points per world, or clear them so the player starts at the world spawn
+    Spawn {
+        #[command(subcommand)]
+        action: SpawnAction,
+    },
+    /// Show, grant or revoke permanent upgrades such as life crystals, the Demon Heart or Torch God's Favor
+    Upgrades {
+        #[command(subcommand)]
+        action: UpgradeAction,
+    },
+    /// Journey mode creative powers saved with the player (god mode, far placement, spawn rate)
+    Powers {
+        #[command(subcommand)]
+        action: PowersAction,
+    },
+    /// Journey mode research. Report, all and category work from an item catalogue you supply with --items, since
+    /// plreditor does not ship the game's item data (see README for the format)
+    Research {
+        #[command(subcommand)]
+        action: ResearchAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum LoadoutAction {
+    /// Overwrite loadout TO with loadout FROM
+    Copy {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        from: usize,
+        to: usize,
+        /// Take loadout FROM from this player instead of the input
+        #[arg(long)]
+        source: Option<PathBuf>,
+    },
+    /// Exchange two loadouts
+    Swap {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        a: usize,
+        b: usize,
+    },
+}
 
-mod edit_plr;
+#[derive(Subcommand)]
+enum BankAction {
+    /// List the occupied slots of one bank (piggy-bank, safe, defenders-forge, void-vault), or of all of them
+    List {
+        #[arg(short, long)]
+        input: PathBuf,
+        bank: Option<Bank>,
+    },
+    /// Put an item in a bank slot, by default the first empty one
+    Add {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        bank: Bank,
+        id: i32,
+        #[arg(long, default_value_t = 1)]
+        stack: i32,
+        #[arg(long, default_value_t = 0)]
+        prefix: u8,
+        #[arg(long)]
+        slot: Option<usize>,
+    },
+    /// Empty a bank slot
+    Remove {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        bank: Bank,
+        slot: usize,
+    },
+    /// Move an item between the inventory and the banks, e.g. `inventory:12 safe` or `piggy-bank:0 inventory:20`
+    Move {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Where to take the item from, as container:slot
+        from: Location,
+        /// Where to put it; without a slot the first empty one is used
+        to: Location,
+    },
+}
+
+#[derive(Subcommand)]
+enum BuffAction {
+    /// List active buffs with their remaining time
+    List {
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Give the player a buff
+    Add {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        buff_type: i32,
+        #[arg(value_parser = parse_duration)]
+        duration: i32,
+    },
+    /// Take a buff away
+    Remove {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        buff_type: i32,
+    },
+    /// Add to the remaining time of an active buff
+    Extend {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        buff_type: i32,
+        #[arg(value_parser = parse_duration)]
+        duration: i32,
+    },
+}
+
+#[derive(Subcommand)]
+enum SpawnAction {
+    /// List the saved spawn point of each world
+    List {
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Remove every saved spawn point
+    Clear {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Remove the spawn point saved for one world
+    Reset {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// World name, or its ID with --id
+        world: String,
+        #[arg(long)]
+        id: bool,
+    },
+}
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    dotenv().expect("Error: .env file not found");
+#[derive(Subcommand)]
+enum UpgradeAction {
+    /// Show every permanent upgrade and whether the player has it
+    List {
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Give upgrades; counted ones (life-crystal, life-fruit, mana-crystal) are maxed out. aegis-crystal is accepted for vital-crystal
+    Grant {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(required = true)]
+        upgrades: Vec<Upgrade>,
+    },
+    /// Take upgrades away; counted ones drop to none
+    Revoke {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(required = true)]
+        upgrades: Vec<Upgrade>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PowersAction {
+    /// Show each power, or "unset" when the file has no record of it
+    List {
+        #[arg(short, long)]
+        input: PathBuf,
+    },
+    /// Set a power: godmode or far-placement to on/off, spawn-rate to a slider position from 0 to 1 (0.5 is normal)
+    Set {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        power: Power,
+        value: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ResearchAction {
+    /// List catalogue items that still need sacrifices for duplication
+    Report {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(long)]
+        items: PathBuf,
+    },
+    /// Fully research every catalogue item
+    All {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(long)]
+        items: PathBuf,
+    },
+    /// Fully research the catalogue items of one category
+    Category {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(long)]
+        items: PathBuf,
+        category: String,
+    },
+    /// Forget all research
+    Clear {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+}
 
-    let filepath = Path::new(&args[1]);
-    let key = env::var("key").expect("Error key not found in env").encode_utf16().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+fn is_json(path: &Path) -> bool {
+    return path.extension().and_then(OsStr::to_str) == Some("json");
+}
+
+// `-` reads an encrypted player from stdin
+fn load(path: &Path, key: &[u8]) -> Result<Plr, Box<dyn Error>> {
+    if path == Path::new("-") {
+        return Ok(Plr::read_from(stdin().lock(), key)?);
+    }
+    let file = File::open(path).map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+    if is_json(path) {
+        return Ok(plreditor::read_json(BufReader::new(file))?);
+    }
+    return Ok(plreditor::read_plr(BufReader::new(file), key)?);
+}
+
+// The raw bytes of `path`, or of stdin for `-`
+fn read_input(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    if path == Path::new("-") {
+        let mut data = Vec::new();
+        stdin().lock().read_to_end(&mut data).map_err(|e| format!("Error reading stdin: {}", e))?;
+        return Ok(data);
+    }
+    return Ok(read(path).map_err(|e| format!("Error reading {}: {}", path.display(), e))?);
+}
+
+fn catalogue(path: &Path) -> Result<Vec<CatalogueItem>, Box<dyn Error>> {
+    return Ok(read_catalogue(path).map_err(|e| format!("Error reading item catalogue {}: {}", path.display(), e))?);
+}
+
+fn save(plr: &Plr, path: &Path, key: &[u8], policy: OverwritePolicy) -> Result<(), Box<dyn Error>> {
+    let mut data = Vec::new();
+    if is_json(path) {
+        plreditor::write_json(plr, &mut data)?;
+    } else {
+        plreditor::write_plr(plr, &mut data, key)?;
+    }
+    write_output(path, &data, policy)?;
+    return Ok(());
+}
+
+fn pointer(path: &str) -> String {
+    return if path.is_empty() {String::new()} else {format!("/{}", path.replace('.', "/"))};
+}
+
+fn format_play_time(ticks: i64) -> String {
+    let seconds = ticks / 10_000_000;
+    return format!("{}h {:02}m {:02}s", seconds / 3600, seconds / 60 % 60, seconds % 60);
+}
+
+fn print_bank(plr: &Plr, bank: Bank) -> Result<(), Box<dyn Error>> {
+    for (slot, item) in plr.bank(bank)?.iter().enumerate().filter(|(_, item)| item.id != 0) {
+        println!("{}:{}\tid {}\tx{}\tprefix {}", bank, slot, item.id, item.stack, item.prefix);
+    }
+    return Ok(());
+}
+
+fn run(cli: Cli) -> Result<ExitCode, Box<dyn Error>> {
+    let key = plreditor::resolve_key(cli.key.as_deref())?;
     let key = key.as_slice();
 
-    match filepath.extension().and_then(OsStr::to_str) {
-        Some("plr") => edit_plr::deconstruct_plr(read(filepath).expect("Error reading plr file"), key),
-        Some("json") => edit_plr::reconstruct_plr(read(filepath).expect("Error reading JSON file"), key),
+    match cli.command {
+        Command::Decrypt { input, output } => {
+            edit_plr::deconstruct_plr(read_input(&input)?, key, output.as_deref(), cli.overwrite)?;
+        }
+        Command::Encrypt { input, output } => {
+            edit_plr::reconstruct_plr(read_input(&input)?, key, output.as_deref(), cli.overwrite)?;
+        }
+        Command::Dump { input } => {
+            let plr = load(&input, key)?;
+            println!("{}", serde_json::to_string_pretty(&plr)?);
+        }
+        Command::Get { input, path } => {
+            let plr = serde_json::to_value(load(&input, key)?)?;
+            let value = plr.pointer(&pointer(&path)).ok_or_else(|| format!("No field at path {:?}", path))?;
+            println!("{}", serde_json::to_string_pretty(value)?);
+        }
+        Command::Set { input, output, path, value } => {
+            let mut plr = serde_json::to_value(load(&input, key)?)?;
+            let target = plr.pointer_mut(&pointer(&path)).ok_or_else(|| format!("No field at path {:?}", path))?;
+            *target = serde_json::from_str(&value).unwrap_or(Value::String(value));
+            let plr: Plr = serde_json::from_value(plr).map_err(|e| format!("Invalid value for {}: {}", path, e))?;
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::Verify { input } => {
+            let report = plreditor::verify_plr(&read_input(&input)?, key)?;
+            let Some(mismatch) = report.mismatch else {
+                println!("{}: OK", input.display());
+                return Ok(ExitCode::SUCCESS);
+            };
+            let byte = |b: Option<u8>| b.map_or(String::from("end of data"), |b| format!("{:#04x}", b));
+            println!("{}: round trip does NOT reproduce the original file", input.display());
+            println!("first difference at decrypted offset {:#x} in {}", mismatch.offset, mismatch.field);
+            println!("original:\t{} ({} bytes)", byte(mismatch.original), report.original_len);
+            println!("rewritten:\t{} ({} bytes)", byte(mismatch.rewritten), report.rewritten_len);
+            return Ok(ExitCode::FAILURE);
+        }
+        Command::Info { input } => {
+            let plr = load(&input, key)?;
+            let layout = Layout::for_version(plr.version)?;
+            println!("name:\t\t{}", plr.name);
+            println!("version:\t{} (Terraria {})", plr.version, layout.release().name);
+            println!("difficulty:\t{}", plr.difficulty);
+            println!("play time:\t{}", format_play_time(plr.play_time));
+            println!("life:\t\t{}/{}", plr.stat_life, plr.stat_life_max);
+            println!("mana:\t\t{}/{}", plr.stat_mana, plr.stat_mana_max);
+            println!("buffs:\t\t{}", plr.buffs.iter().filter(|b| !b.is_empty()).count());
+            println!("spawn points:\t{}", plr.spawn_points.len());
+        }
+        Command::Loadout { action: LoadoutAction::Copy { input, output, from, to, source } } => {
+            let mut plr = load(&input, key)?;
+            let loadout = match source {
+                Some(source) => load(&source, key)?.loadout(from)?,
+                None => plr.loadout(from)?,
+            };
+            plr.set_loadout(to, loadout)?;
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::Loadout { action: LoadoutAction::Swap { input, output, a, b } } => {
+            let mut plr = load(&input, key)?;
+            plr.swap_loadouts(a, b)?;
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::Bank { action: BankAction::List { input, bank } } => {
+            let plr = load(&input, key)?;
+            let layout = Layout::for_version(plr.version)?;
+            match bank {
+                Some(bank) => print_bank(&plr, bank)?,
+                None => {
+                    for bank in Bank::ALL.into_iter().filter(|b| b.exists(&layout)) {
+                        print_bank(&plr, bank)?;
+                    }
+                }
+            }
+        }
+        Command::Bank { action: BankAction::Add { input, output, bank, id, stack, prefix, slot } } => {
+            let mut plr = load(&input, key)?;
+            let item = Item { id, stack, prefix, favourites: false, unknown: false };
+            let slot = plr.add_item(Container::Bank(bank), slot, item)?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("added to {}:{}", bank, slot);
+        }
+        Command::Bank { action: BankAction::Remove { input, output, bank, slot } } => {
+            let mut plr = load(&input, key)?;
+            let item = plr.remove_item(Container::Bank(bank), slot)?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("removed id {} x{} from {}:{}", item.id, item.stack, bank, slot);
+        }
+        Command::Bank { action: BankAction::Move { input, output, from, to } } => {
+            let mut plr = load(&input, key)?;
+            let from_slot = from.slot.ok_or_else(|| format!("{} needs a slot to move from, e.g. {}:0", from.container, from.container))?;
+            let slot = plr.move_item(from.container, from_slot, to.container, to.slot)?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("moved to {}:{}", to.container, slot);
+        }
+        Command::Buff { action: BuffAction::List { input } } => {
+            let plr = load(&input, key)?;
+            let slots = Layout::for_version(plr.version)?.buff_slots();
+            for buff in plr.buffs.iter().filter(|b| !b.is_empty()) {
+                println!("{}\t{}", buff.buff_type, format_duration(buff.time));
+            }
+            println!("{}/{} slots used", plr.buffs.iter().filter(|b| !b.is_empty()).count(), slots);
+        }
+        Command::Buff { action: BuffAction::Add { input, output, buff_type, duration } } => {
+            let mut plr = load(&input, key)?;
+            plr.add_buff(buff_type, duration)?;
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::Buff { action: BuffAction::Remove { input, output, buff_type } } => {
+            let mut plr = load(&input, key)?;
+            plr.remove_buff(buff_type)?;
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::Buff { action: BuffAction::Extend { input, output, buff_type, duration } } => {
+            let mut plr = load(&input, key)?;
+            let time = plr.extend_buff(buff_type, duration)?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("{}\t{}", buff_type, format_duration(time));
+        }
+        Command::Spawn { action: SpawnAction::List { input } } => {
+            let plr = load(&input, key)?;
+            for sp in plr.spawn_points.iter() {
+                println!("{}\t{:?}\t{}, {}", sp.world_id, sp.world_name, sp.x, sp.y);
+            }
+        }
+        Command::Spawn { action: SpawnAction::Clear { input, output } } => {
+            let mut plr = load(&input, key)?;
+            let removed = plr.clear_spawn_points();
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("removed {} spawn points", removed);
+        }
+        Command::Spawn { action: SpawnAction::Reset { input, output, world, id } } => {
+            let mut plr = load(&input, key)?;
+            let world = if id {World::Id(world.parse().map_err(|_| format!("Invalid world ID {:?}", world))?)} else {World::Name(world)};
+            let removed = plr.reset_spawn_point(&world)?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("removed {} spawn points", removed);
+        }
+        Command::Upgrades { action: UpgradeAction::List { input } } => {
+            let plr = load(&input, key)?;
+            let u = plr.permanent_upgrades();
+            println!("life-crystal\t{}/{}", u.life_crystals, plreditor::upgrades::MAX_LIFE_CRYSTALS);
+            println!("life-fruit\t{}/{}", u.life_fruits, plreditor::upgrades::MAX_LIFE_FRUITS);
+            println!("mana-crystal\t{}/{}", u.mana_crystals, plreditor::upgrades::MAX_MANA_CRYSTALS);
+            for upgrade in Upgrade::ALL.into_iter().filter(|u| !u.is_counted()) {
+                println!("{}\t{}", upgrade, if plr.has_upgrade(upgrade) {"yes"} else {"no"});
+            }
+        }
+        Command::Upgrades { action: UpgradeAction::Grant { input, output, upgrades } } => {
+            let mut plr = load(&input, key)?;
+            for upgrade in upgrades {
+                plr.set_upgrade(upgrade, true)?;
+            }
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::Upgrades { action: UpgradeAction::Revoke { input, output, upgrades } } => {
+            let mut plr = load(&input, key)?;
+            for upgrade in upgrades {
+                plr.set_upgrade(upgrade, false)?;
+            }
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::ConvertMode { input, output, difficulty } => {
+            let mut plr = load(&input, key)?;
+            let from = plr.difficulty;
+            plr.convert_mode(difficulty)?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("converted from {} to {}", from, difficulty);
+        }
+        Command::Powers { action: PowersAction::List { input } } => {
+            let plr = load(&input, key)?;
+            let powers = plr.creative_powers;
+            let unset = || String::from("unset");
+            println!("{}\t{}", Power::Godmode, powers.godmode.map_or_else(unset, |on| String::from(if on {"on"} else {"off"})));
+            println!("{}\t{}", Power::FarPlacement, powers.far_placement_range.map_or_else(unset, |on| String::from(if on {"on"} else {"off"})));
+            println!("{}\t{}", Power::SpawnRate, powers.spawn_rate.map_or_else(unset, |rate| rate.to_string()));
+        }
+        Command::Powers { action: PowersAction::Set { input, output, power, value } } => {
+            let mut plr = load(&input, key)?;
+            plr.set_power(PowerSetting::parse(power, &value)?)?;
+            save(&plr, &output, key, cli.overwrite)?;
+        }
+        Command::Research { action: ResearchAction::Report { input, items } } => {
+            let plr = load(&input, key)?;
+            let catalogue = catalogue(&items)?;
+            let missing = missing_research(&plr, &catalogue);
+            for (item, count) in missing.iter() {
+                println!("{}\t{}/{}", item.name, count, item.research);
+            }
+            println!("{} of {} researchable items still need sacrifices", missing.len(), catalogue.iter().filter(|i| i.research > 0).count());
+        }
+        Command::Research { action: ResearchAction::All { input, output, items } } => {
+            let mut plr = load(&input, key)?;
+            let changed = plr.research_items(&catalogue(&items)?, None)?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("researched {} items", changed);
+        }
+        Command::Research { action: ResearchAction::Category { input, output, items, category } } => {
+            let mut plr = load(&input, key)?;
+            let changed = plr.research_items(&catalogue(&items)?, Some(&category))?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("researched {} items", changed);
+        }
+        Command::Research { action: ResearchAction::Clear { input, output } } => {
+            let mut plr = load(&input, key)?;
+            let cleared = plr.clear_research()?;
+            save(&plr, &output, key, cli.overwrite)?;
+            println!("cleared {} items", cleared);
+        }
+    }
+    return Ok(ExitCode::SUCCESS);
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // .env is optional; only a present but unreadable one is worth reporting
+    if let Err(e) = dotenv() {
+        if !e.not_found() {
+            eprintln!("Error loading .env: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
 
-        _ => edit_plr::Plr::default(),
+    return match run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
     };
 }
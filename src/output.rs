@@ -0,0 +1,152 @@
+use std::fmt;
+use std::fs::{
+    rename,
+    OpenOptions,
+};
+use std::io::{
+    ErrorKind,
+    Write,
+};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::PlrError;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    #[default]
+    Refuse,
+    Force,
+    Backup,
+}
+
+impl FromStr for OverwritePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "refuse" => Ok(OverwritePolicy::Refuse),
+            "force" => Ok(OverwritePolicy::Force),
+            "backup" => Ok(OverwritePolicy::Backup),
+            _ => Err(format!("Unknown overwrite policy {:?} (expected refuse, force or backup)", s)),
+        };
+    }
+}
+
+impl fmt::Display for OverwritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            OverwritePolicy::Refuse => "refuse",
+            OverwritePolicy::Force => "force",
+            OverwritePolicy::Backup => "backup",
+        });
+    }
+}
+
+const RESERVED_WINDOWS_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+// In-game names may contain anything, so keep them to a single file name component that is valid on every platform
+pub fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) {'_'} else {c})
+        .collect();
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if cleaned.is_empty() {
+        return String::from("unnamed");
+    }
+    if RESERVED_WINDOWS_NAMES.iter().any(|r| r.eq_ignore_ascii_case(cleaned)) {
+        return format!("_{}", cleaned);
+    }
+    return cleaned.to_owned();
+}
+
+pub fn default_json_file_name(name: &str) -> String {
+    return format!("DECRYPTED_{}.json", sanitize_file_name(name));
+}
+
+pub fn default_plr_file_name(name: &str) -> String {
+    return format!("COPY_{}.plr", sanitize_file_name(name));
+}
+
+// Backup moves an existing file to `<path>.bak`, replacing any older backup
+pub fn write_output(path: &Path, data: &[u8], policy: OverwritePolicy) -> Result<(), PlrError> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    match policy {
+        OverwritePolicy::Refuse => {
+            options.create_new(true);
+        }
+        OverwritePolicy::Force => {
+            options.create(true).truncate(true);
+        }
+        OverwritePolicy::Backup => {
+            if path.exists() {
+                let mut backup = path.as_os_str().to_owned();
+                backup.push(".bak");
+                rename(path, backup)?;
+            }
+            options.create(true).truncate(true);
+        }
+    }
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        ErrorKind::AlreadyExists => PlrError::OutputExists { path: path.to_path_buf() },
+        _ => PlrError::Io(e),
+    })?;
+    file.write_all(data)?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn file_names_are_a_single_portable_component() {
+        for (name, file_name) in [
+            ("Guide", "Guide"),
+            ("a/b", "a_b"),
+            ("a\\b:c", "a_b_c"),
+            ("..", "unnamed"),
+            ("../x", "_x"),
+            ("", "unnamed"),
+            ("  ", "unnamed"),
+            ("CON", "_CON"),
+            ("lpt1", "_lpt1"),
+            ("CONSOLE", "CONSOLE"),
+        ] {
+            assert_eq!(sanitize_file_name(name), file_name, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn existing_output_follows_the_overwrite_policy() {
+        let dir = std::env::temp_dir().join(format!("plreditor-output-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("player.json");
+        let backup = dir.join("player.json.bak");
+
+        write_output(&path, b"first", OverwritePolicy::Refuse).unwrap();
+        assert!(matches!(write_output(&path, b"second", OverwritePolicy::Refuse), Err(PlrError::OutputExists { .. })));
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+
+        write_output(&path, b"2nd", OverwritePolicy::Force).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"2nd");
+        assert!(!backup.exists());
+
+        write_output(&path, b"third", OverwritePolicy::Backup).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"third");
+        assert_eq!(fs::read(&backup).unwrap(), b"2nd");
+        // An older backup is replaced
+        write_output(&path, b"fourth", OverwritePolicy::Backup).unwrap();
+        assert_eq!(fs::read(&backup).unwrap(), b"third");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::difficulty::Difficulty;
+use crate::edit_plr::Plr;
+use crate::error::PlrError;
+use crate::layout::{
+    Feature,
+    Layout,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Power {
+    Godmode,
+    FarPlacement,
+    SpawnRate,
+}
+
+impl Power {
+    pub const ALL: [Power; 3] = [Power::Godmode, Power::FarPlacement, Power::SpawnRate];
+
+    fn name(&self) -> &'static str {
+        return match self {
+            Power::Godmode => "godmode",
+            Power::FarPlacement => "far-placement",
+            Power::SpawnRate => "spawn-rate",
+        };
+    }
+}
+
+impl FromStr for Power {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Power::ALL
+            .into_iter()
+            .find(|p| p.name() == s)
+            .ok_or_else(|| format!("Unknown power {:?} (expected one of {})", s, Power::ALL.map(|p| p.name()).join(", ")));
+    }
+}
+
+impl fmt::Display for Power {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(self.name());
+    }
+}
+
+// A power with the value to give it: on/off for the toggles, the slider position from 0 to 1 for the spawn rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerSetting {
+    Godmode(bool),
+    FarPlacement(bool),
+    SpawnRate(f32),
+}
+
+impl PowerSetting {
+    pub fn parse(power: Power, value: &str) -> Result<PowerSetting, String> {
+        let toggle = || match value {
+            "on" | "true" => Ok(true),
+            "off" | "false" => Ok(false),
+            _ => Err(format!("Invalid value {:?} for {} (expected on or off)", value, power)),
+        };
+        return match power {
+            Power::Godmode => toggle().map(PowerSetting::Godmode),
+            Power::FarPlacement => toggle().map(PowerSetting::FarPlacement),
+            Power::SpawnRate => match value.parse::<f32>() {
+                Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(PowerSetting::SpawnRate(rate)),
+                _ => Err(format!("Invalid value {:?} for {} (expected a number from 0 to 1)", value, power)),
+            },
+        };
+    }
+}
+
+impl Plr {
+    fn check_powers(&self) -> Result<(), PlrError> {
+        if !Layout::for_version(self.version)?.has(Feature::CreativePowers) {
+            return Err(PlrError::InvalidField {
+                field: "creative_powers",
+                reason: format!("version {} players have no creative powers", self.version),
+            });
+        }
+        if self.difficulty != Difficulty::Journey {
+            return Err(PlrError::InvalidField {
+                field: "creative_powers",
+                reason: String::from("only Journey mode players have creative powers"),
+            });
+        }
+        return Ok(());
+    }
+
+    pub fn set_power(&mut self, setting: PowerSetting) -> Result<(), PlrError> {
+        self.check_powers()?;
+        match setting {
+            PowerSetting::Godmode(on) => self.creative_powers.godmode = Some(on),
+            PowerSetting::FarPlacement(on) => self.creative_powers.far_placement_range = Some(on),
+            PowerSetting::SpawnRate(rate) => self.creative_powers.spawn_rate = Some(rate),
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::de::{
+    MapAccess,
+    Visitor,
+};
+use serde::ser::SerializeMap;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+
+use crate::difficulty::Difficulty;
+use crate::edit_plr::{
+    Plr,
+    ResearchEntry,
+};
+use crate::error::PlrError;
+use crate::layout::{
+    Feature,
+    Layout,
+};
+
+// Journey mode research as item name -> sacrificed count. Kept in file order so unedited players round trip
+// byte for byte; shown in JSON as an object.
+#[derive(Debug, Default, Clone)]
+pub struct Research {
+    pub entries: Vec<ResearchEntry>,
+}
+
+impl Research {
+    pub fn get(&self, item: &str) -> i32 {
+        return self.entries.iter().find(|e| e.item == item).map_or(0, |e| e.count);
+    }
+
+    // Replaces the count of an already researched item in place, otherwise appends it
+    pub fn set(&mut self, item: &str, count: i32) {
+        match self.entries.iter_mut().find(|e| e.item == item) {
+            Some(entry) => entry.count = count,
+            None => self.entries.push(ResearchEntry { item: item.to_owned(), count }),
+        }
+    }
+
+    pub fn remove(&mut self, item: &str) -> Option<i32> {
+        let index = self.entries.iter().position(|e| e.item == item)?;
+        return Some(self.entries.remove(index).count);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i32)> {
+        return self.entries.iter().map(|e| (e.item.as_str(), e.count));
+    }
+}
+
+impl Serialize for Research {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for entry in self.entries.iter() {
+            map.serialize_entry(&entry.item, &entry.count)?;
+        }
+        return map.end();
+    }
+}
+
+struct ResearchVisitor;
+
+impl<'de> Visitor<'de> for ResearchVisitor {
+    type Value = Research;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("a map of item names to research counts");
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Research, A::Error> {
+        let mut research = Research::default();
+        while let Some((item, count)) = access.next_entry::<String, i32>()? {
+            research.entries.push(ResearchEntry { item, count });
+        }
+        return Ok(research);
+    }
+}
+
+impl<'de> Deserialize<'de> for Research {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return deserializer.deserialize_map(ResearchVisitor);
+    }
+}
+
+// One entry of an item catalogue file: a JSON array of these, e.g.
+// [{"name": "DirtBlock", "research": 100, "category": "blocks"}]. `research` is the sacrifices needed to unlock
+// duplication, 0 for items that cannot be researched.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CatalogueItem {
+    pub name: String,
+    pub research: i32,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+pub fn read_catalogue(path: &Path) -> Result<Vec<CatalogueItem>, PlrError> {
+    let file = File::open(path)?;
+    return Ok(serde_json::from_reader(BufReader::new(file))?);
+}
+
+// Researchable catalogue items still short of full duplication, with how many this player has sacrificed
+pub fn missing_research<'a>(plr: &Plr, catalogue: &'a [CatalogueItem]) -> Vec<(&'a CatalogueItem, i32)> {
+    return catalogue
+        .iter()
+        .filter(|item| item.research > 0)
+        .map(|item| (item, plr.research.get(&item.name)))
+        .filter(|(item, count)| *count < item.research)
+        .collect();
+}
+
+impl Plr {
+    fn check_research(&self) -> Result<(), PlrError> {
+        if !Layout::for_version(self.version)?.has(Feature::Research) {
+            return Err(PlrError::InvalidField {
+                field: "research",
+                reason: format!("version {} players have no research", self.version),
+            });
+        }
+        if self.difficulty != Difficulty::Journey {
+            return Err(PlrError::InvalidField {
+                field: "research",
+                reason: String::from("only Journey mode players can research items"),
+            });
+        }
+        return Ok(());
+    }
+
+    // Fully researches every catalogue item in `category`, or every item when it is None; returns how many changed
+    pub fn research_items(&mut self, catalogue: &[CatalogueItem], category: Option<&str>) -> Result<usize, PlrError> {
+        self.check_research()?;
+        let items: Vec<&CatalogueItem> = catalogue
+            .iter()
+            .filter(|item| item.research > 0)
+            .filter(|item| category.is_none() || item.category.as_deref() == category)
+            .collect();
+        if items.is_empty() {
+            return Err(PlrError::InvalidField {
+                field: "research",
+                reason: match category {
+                    Some(category) => format!("no researchable items in category {:?}", category),
+                    None => String::from("no researchable items in the catalogue"),
+                },
+            });
+        }
+        let mut changed = 0;
+        for item in items {
+            if self.research.get(&item.name) < item.research {
+                self.research.set(&item.name, item.research);
+                changed += 1;
+            }
+        }
+        return Ok(changed);
+    }
+
+    pub fn clear_research(&mut self) -> Result<usize, PlrError> {
+        self.check_research()?;
+        let cleared = self.research.len();
+        self.research.clear();
+        return Ok(cleared);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalogue() -> Vec<CatalogueItem> {
+        let item = |name: &str, research: i32, category: Option<&str>| CatalogueItem {
+            name: name.to_owned(),
+            research,
+            category: category.map(str::to_owned),
+        };
+        return vec![
+            item("DirtBlock", 100, Some("blocks")),
+            item("StoneBlock", 100, Some("blocks")),
+            item("Wood", 100, None),
+            item("CopperShortsword", 1, Some("weapons")),
+            item("Zenith", 0, Some("weapons")), // Not researchable
+        ];
+    }
+
+    fn player() -> Plr {
+        let mut plr = Plr { version: 279, difficulty: Difficulty::Journey, ..Plr::default() };
+        plr.research.set("DirtBlock", 100);
+        plr.research.set("StoneBlock", 40);
+        plr.research.set("CopperShortsword", 5);
+        return plr;
+    }
+
+    #[test]
+    fn missing_research_lists_researchable_items_short_of_duplication() {
+        let catalogue = catalogue();
+        let missing: Vec<(&str, i32)> = missing_research(&player(), &catalogue).iter().map(|(item, count)| (item.name.as_str(), *count)).collect();
+        assert_eq!(missing, [("StoneBlock", 40), ("Wood", 0)]);
+    }
+
+    #[test]
+    fn research_items_fills_in_what_is_missing() {
+        let mut plr = player();
+        assert_eq!(plr.research_items(&catalogue(), Some("blocks")).unwrap(), 1);
+        assert_eq!(plr.research.get("StoneBlock"), 100);
+        assert_eq!(plr.research.get("Wood"), 0);
+
+        assert_eq!(plr.research_items(&catalogue(), None).unwrap(), 1);
+        assert_eq!(plr.research.get("Wood"), 100);
+        // Counts past what duplication needs are left alone
+        assert_eq!(plr.research.get("CopperShortsword"), 5);
+        assert_eq!(plr.research.get("Zenith"), 0);
+        assert!(missing_research(&plr, &catalogue()).is_empty());
+        assert_eq!(plr.research_items(&catalogue(), None).unwrap(), 0);
+    }
+
+    #[test]
+    fn research_items_is_refused_when_nothing_can_be_researched() {
+        let mut plr = player();
+        assert!(plr.research_items(&catalogue(), Some("tools")).is_err());
+        assert!(plr.research_items(&[], None).is_err());
+        let mut classic = Plr { difficulty: Difficulty::Classic, ..player() };
+        assert!(classic.research_items(&catalogue(), None).is_err());
+        let mut old = Plr { version: 217, ..player() };
+        assert!(old.research_items(&catalogue(), None).is_err());
+    }
+}
@@ -0,0 +1,770 @@
+use std::io::{
+    Read,
+    Write,
+};
+
+use crate::edit_plr::{
+    Armor,
+    Buff,
+    CountingReader,
+    CreativePowers,
+    Dyes,
+    Equipment,
+    Item,
+    Loadout,
+    PlrPacker,
+    PlrUnpacker,
+    ResearchEntry,
+    Rgb,
+    SpawnPoint,
+    SuperCart,
+    TemporaryItems,
+};
+use crate::difficulty::Difficulty;
+use crate::error::PlrError;
+use crate::layout::{
+    Feature,
+    Layout,
+};
+use crate::research::Research;
+use crate::storage::BANK_SLOTS;
+
+// A value with a fixed binary encoding, independent of the file version
+pub(crate) trait PlrField: Sized {
+    // Encoded size in bytes when it never varies; lets arrays record per-element spans
+    const WIDTH: Option<u64> = None;
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError>;
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError>;
+
+    // Where `offset` falls inside a value of fixed WIDTH, like "[2]" or ".armor.head"; empty for a single value
+    fn path_at(_offset: u64) -> String {
+        return String::new();
+    }
+}
+
+macro_rules! primitive_field {
+    ($($t:ty => $width:literal, $read:ident, $write:ident;)*) => {
+        $(
+            impl PlrField for $t {
+                const WIDTH: Option<u64> = Some($width);
+
+                fn read<R: Read>(reader: &mut CountingReader<R>, _field: &'static str) -> Result<Self, PlrError> {
+                    return PlrUnpacker::$read(reader);
+                }
+
+                fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+                    PlrPacker::$write(writer, *self)?;
+                    return Ok(());
+                }
+            }
+        )*
+    };
+}
+
+primitive_field! {
+    i8 => 1, r_i8, w_i8;
+    u8 => 1, r_u8, w_u8;
+    u16 => 2, r_u16, w_u16;
+    i32 => 4, r_i32, w_i32;
+    u32 => 4, r_u32, w_u32;
+    i64 => 8, r_i64, w_i64;
+    f32 => 4, r_f32, w_f32;
+    bool => 1, r_bool, w_bool;
+}
+
+impl PlrField for String {
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        return PlrUnpacker::r_string(reader, field);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        PlrPacker::w_string(writer, self)?;
+        return Ok(());
+    }
+}
+
+impl<T: PlrField + Default + Copy, const N: usize> PlrField for [T; N] {
+    const WIDTH: Option<u64> = match T::WIDTH {
+        Some(width) => Some(width * N as u64),
+        None => None,
+    };
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        let read_values = |reader: &mut CountingReader<R>| {
+            let mut values = [T::default(); N];
+            for value in values.iter_mut() {
+                *value = T::read(reader, field)?;
+            }
+            return Ok(values);
+        };
+        return match T::WIDTH {
+            Some(width) => reader.read_run(field, width, T::path_at, read_values),
+            None => read_values(reader),
+        };
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        for value in self.iter() {
+            value.write(writer)?;
+        }
+        return Ok(());
+    }
+
+    fn path_at(offset: u64) -> String {
+        let width = T::WIDTH.unwrap_or(1);
+        return format!("[{}]{}", offset / width, T::path_at(offset % width));
+    }
+}
+
+impl PlrField for Equipment {
+    const WIDTH: Option<u64> = Some(5);
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        return Ok(Equipment {
+            id: i32::read(reader, field)?,
+            prefix: u8::read(reader, field)?,
+        });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        self.id.write(writer)?;
+        self.prefix.write(writer)?;
+        return Ok(());
+    }
+}
+
+impl PlrField for Rgb {
+    const WIDTH: Option<u64> = Some(3);
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        return Ok(Rgb {
+            r: u8::read(reader, field)?,
+            g: u8::read(reader, field)?,
+            b: u8::read(reader, field)?,
+        });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        self.r.write(writer)?;
+        self.g.write(writer)?;
+        self.b.write(writer)?;
+        return Ok(());
+    }
+}
+
+// The inventory encoding; banks store items differently, see `BankSlots`
+impl PlrField for Item {
+    const WIDTH: Option<u64> = Some(10);
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        let id = i32::read(reader, field)?;
+        return Ok(Item {
+            id,
+            stack: i32::read(reader, field)?,
+            prefix: u8::read(reader, field)?,
+            favourites: bool::read(reader, field)?,
+            unknown: Item::is_unknown_id(id),
+        });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        self.id.write(writer)?;
+        self.stack.write(writer)?;
+        self.prefix.write(writer)?;
+        self.favourites.write(writer)?;
+        return Ok(());
+    }
+}
+
+impl PlrField for ResearchEntry {
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        return Ok(ResearchEntry {
+            item: String::read(reader, field)?,
+            count: i32::read(reader, field)?,
+        });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        self.item.write(writer)?;
+        self.count.write(writer)?;
+        return Ok(());
+    }
+}
+
+// A bits byte saying which slots follow, each as id, stack and prefix
+impl PlrField for TemporaryItems {
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        let bits = u8::read(reader, field)?;
+        let mut slots: [Option<Item>; 4] = [None; 4];
+        for (bit, slot) in slots.iter_mut().enumerate() {
+            if bits & (1 << bit) != 0 {
+                let id = i32::read(reader, field)?;
+                *slot = Some(Item {
+                    id,
+                    stack: i32::read(reader, field)?,
+                    prefix: u8::read(reader, field)?,
+                    favourites: false,
+                    unknown: Item::is_unknown_id(id),
+                });
+            }
+        }
+        let [mouse, creative_sacrifice, guide, reforge] = slots;
+        return Ok(TemporaryItems { mouse, creative_sacrifice, guide, reforge });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        let slots = [self.mouse, self.creative_sacrifice, self.guide, self.reforge];
+        let bits = slots.iter().enumerate().fold(0u8, |bits, (bit, slot)| if slot.is_some() {bits | 1 << bit} else {bits});
+        bits.write(writer)?;
+        for item in slots.iter().flatten() {
+            item.id.write(writer)?;
+            item.stack.write(writer)?;
+            item.prefix.write(writer)?;
+        }
+        return Ok(());
+    }
+}
+
+const GODMODE_POWER: u16 = 5;
+const FAR_PLACEMENT_RANGE_POWER: u16 = 11;
+const SPAWN_RATE_POWER: u16 = 14;
+
+// Records of (true, u16 power id, payload) ending with false. The payload size depends on the power, so an
+// unknown id cannot be skipped.
+impl PlrField for CreativePowers {
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        let mut powers = CreativePowers::default();
+        while bool::read(reader, field)? {
+            match u16::read(reader, field)? {
+                GODMODE_POWER => powers.godmode = Some(bool::read(reader, field)?),
+                FAR_PLACEMENT_RANGE_POWER => powers.far_placement_range = Some(bool::read(reader, field)?),
+                SPAWN_RATE_POWER => powers.spawn_rate = Some(f32::read(reader, field)?),
+                id => {
+                    return Err(PlrError::InvalidField {
+                        field,
+                        reason: format!("unknown creative power id {}", id),
+                    });
+                }
+            }
+        }
+        return Ok(powers);
+    }
+
+    // In power id order, as the game writes them
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        if let Some(godmode) = self.godmode {
+            true.write(writer)?;
+            GODMODE_POWER.write(writer)?;
+            godmode.write(writer)?;
+        }
+        if let Some(far_placement_range) = self.far_placement_range {
+            true.write(writer)?;
+            FAR_PLACEMENT_RANGE_POWER.write(writer)?;
+            far_placement_range.write(writer)?;
+        }
+        if let Some(spawn_rate) = self.spawn_rate {
+            true.write(writer)?;
+            SPAWN_RATE_POWER.write(writer)?;
+            spawn_rate.write(writer)?;
+        }
+        false.write(writer)?;
+        return Ok(());
+    }
+}
+
+impl PlrField for Difficulty {
+    const WIDTH: Option<u64> = Some(1);
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        return Ok(Difficulty::from_byte(u8::read(reader, field)?));
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        return self.to_byte().write(writer);
+    }
+}
+
+// Bit 0 unlocked, bit 1 enabled
+impl PlrField for SuperCart {
+    const WIDTH: Option<u64> = Some(1);
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        let bits = u8::read(reader, field)?;
+        return Ok(SuperCart { unlocked: bits & 1 != 0, enabled: bits & 2 != 0 });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        return (self.unlocked as u8 | (self.enabled as u8) << 1).write(writer);
+    }
+}
+
+// Loadout slots hold id, stack and prefix, without the favourite flag of inventory items
+fn read_loadout_item<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Item, PlrError> {
+    let id = i32::read(reader, field)?;
+    return Ok(Item {
+        id,
+        stack: i32::read(reader, field)?,
+        prefix: u8::read(reader, field)?,
+        favourites: false,
+        unknown: Item::is_unknown_id(id),
+    });
+}
+
+fn write_loadout_item<W: Write>(writer: &mut W, item: &Item) -> Result<(), PlrError> {
+    item.id.write(writer)?;
+    item.stack.write(writer)?;
+    item.prefix.write(writer)?;
+    return Ok(());
+}
+
+// Slot names in the order `Armor::iter` and `Dyes::iter` give them
+fn armor_slot(index: u64) -> String {
+    return match index {
+        0 => String::from("head"),
+        1 => String::from("body"),
+        2 => String::from("legs"),
+        3..=9 => format!("accessories[{}]", index - 3),
+        10 => String::from("vanity_head"),
+        11 => String::from("vanity_body"),
+        12 => String::from("vanity_legs"),
+        _ => format!("vanity_accessories[{}]", index - 13),
+    };
+}
+
+fn dye_slot(index: u64) -> String {
+    return match index {
+        0..=2 => armor_slot(index),
+        _ => format!("accessories[{}]", index - 3),
+    };
+}
+
+const LOADOUT_ITEM_WIDTH: u64 = 9;
+const LOADOUT_DYES_OFFSET: u64 = 20 * LOADOUT_ITEM_WIDTH;
+const LOADOUT_HIDE_VISUAL_OFFSET: u64 = LOADOUT_DYES_OFFSET + 10 * LOADOUT_ITEM_WIDTH;
+
+impl PlrField for Loadout {
+    const WIDTH: Option<u64> = Some(LOADOUT_HIDE_VISUAL_OFFSET + 10);
+
+    fn read<R: Read>(reader: &mut CountingReader<R>, field: &'static str) -> Result<Self, PlrError> {
+        let mut armor: [Item; 20] = [Item::default(); 20];
+        for item in armor.iter_mut() {
+            *item = read_loadout_item(reader, field)?;
+        }
+        let mut dyes: [Item; 10] = [Item::default(); 10];
+        for item in dyes.iter_mut() {
+            *item = read_loadout_item(reader, field)?;
+        }
+        let [head, body, legs, a0, a1, a2, a3, a4, a5, a6, vanity_head, vanity_body, vanity_legs, v0, v1, v2, v3, v4, v5, v6] = armor;
+        let [dye_head, dye_body, dye_legs, d0, d1, d2, d3, d4, d5, d6] = dyes;
+        return Ok(Loadout {
+            armor: Armor {
+                head,
+                body,
+                legs,
+                accessories: [a0, a1, a2, a3, a4, a5, a6],
+                vanity_head,
+                vanity_body,
+                vanity_legs,
+                vanity_accessories: [v0, v1, v2, v3, v4, v5, v6],
+            },
+            dyes: Dyes {
+                head: dye_head,
+                body: dye_body,
+                legs: dye_legs,
+                accessories: [d0, d1, d2, d3, d4, d5, d6],
+            },
+            hide_visual: <[bool; 10]>::read(reader, field)?,
+        });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), PlrError> {
+        for item in self.armor.iter().chain(self.dyes.iter()) {
+            write_loadout_item(writer, item)?;
+        }
+        self.hide_visual.write(writer)?;
+        return Ok(());
+    }
+
+    fn path_at(offset: u64) -> String {
+        if offset < LOADOUT_DYES_OFFSET {
+            return format!(".armor.{}", armor_slot(offset / LOADOUT_ITEM_WIDTH));
+        }
+        if offset < LOADOUT_HIDE_VISUAL_OFFSET {
+            return format!(".dyes.{}", dye_slot((offset - LOADOUT_DYES_OFFSET) / LOADOUT_ITEM_WIDTH));
+        }
+        return format!(".hide_visual[{}]", offset - LOADOUT_HIDE_VISUAL_OFFSET);
+    }
+}
+
+// How one field of type `T` is read and written, for encodings that depend on the file version or need validating
+pub(crate) trait Codec<T> {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, layout: &Layout, field: &'static str) -> Result<T, PlrError>;
+    fn write<W: Write>(&self, writer: &mut W, value: &T, layout: &Layout, field: &'static str) -> Result<(), PlrError>;
+}
+
+// The default codec: the field's own `PlrField` encoding
+pub(crate) struct Plain;
+
+impl<T: PlrField> Codec<T> for Plain {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, _layout: &Layout, field: &'static str) -> Result<T, PlrError> {
+        return T::read(reader, field);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &T, _layout: &Layout, _field: &'static str) -> Result<(), PlrError> {
+        return value.write(writer);
+    }
+}
+
+// A bool saying whether the value follows
+pub(crate) struct Flagged;
+
+impl<T: PlrField> Codec<Option<T>> for Flagged {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, _layout: &Layout, field: &'static str) -> Result<Option<T>, PlrError> {
+        return Ok(if bool::read(reader, field)? {Some(T::read(reader, field)?)} else {None});
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Option<T>, _layout: &Layout, _field: &'static str) -> Result<(), PlrError> {
+        value.is_some().write(writer)?;
+        if let Some(value) = value {
+            value.write(writer)?;
+        }
+        return Ok(());
+    }
+}
+
+// An i32 count followed by that many values
+pub(crate) struct Counted;
+
+impl<T: PlrField> Codec<Vec<T>> for Counted {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, _layout: &Layout, field: &'static str) -> Result<Vec<T>, PlrError> {
+        let count = i32::read(reader, field)?;
+        let count = usize::try_from(count).map_err(|_| PlrError::InvalidField {
+            field,
+            reason: format!("negative count {}", count),
+        })?;
+        let mut values: Vec<T> = Vec::new();
+        for index in 0..count {
+            reader.mark_indexed(field, index);
+            values.push(T::read(reader, field)?);
+        }
+        return Ok(values);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Vec<T>, _layout: &Layout, field: &'static str) -> Result<(), PlrError> {
+        let count = i32::try_from(value.len()).map_err(|_| PlrError::InvalidField {
+            field,
+            reason: format!("{} entries do not fit an i32 count", value.len()),
+        })?;
+        count.write(writer)?;
+        for v in value.iter() {
+            v.write(writer)?;
+        }
+        return Ok(());
+    }
+}
+
+impl Codec<Research> for Counted {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, layout: &Layout, field: &'static str) -> Result<Research, PlrError> {
+        return Ok(Research { entries: self.read(reader, layout, field)? });
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Research, layout: &Layout, field: &'static str) -> Result<(), PlrError> {
+        return self.write(writer, &value.entries, layout, field);
+    }
+}
+
+// A run of not yet decoded bytes, written back verbatim
+pub(crate) struct Bytes(pub usize);
+
+impl Codec<Vec<u8>> for Bytes {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, _layout: &Layout, _field: &'static str) -> Result<Vec<u8>, PlrError> {
+        return PlrUnpacker::r_bytes(reader, self.0);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Vec<u8>, _layout: &Layout, field: &'static str) -> Result<(), PlrError> {
+        if value.len() != self.0 {
+            return Err(PlrError::InvalidField {
+                field,
+                reason: format!("expected {} bytes, found {}", self.0, value.len()),
+            });
+        }
+        PlrPacker::w_bytes(writer, value)?;
+        return Ok(());
+    }
+}
+
+// Everything left after the last decoded field
+pub(crate) struct Rest;
+
+impl Codec<Vec<u8>> for Rest {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, _layout: &Layout, _field: &'static str) -> Result<Vec<u8>, PlrError> {
+        return PlrUnpacker::r_to_end(reader);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Vec<u8>, _layout: &Layout, _field: &'static str) -> Result<(), PlrError> {
+        PlrPacker::w_bytes(writer, value)?;
+        return Ok(());
+    }
+}
+
+// 40 items without the favourite flag, unless the given feature adds it
+pub(crate) struct BankSlots {
+    pub favourites: Option<Feature>,
+}
+
+impl Codec<Vec<Item>> for BankSlots {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, layout: &Layout, field: &'static str) -> Result<Vec<Item>, PlrError> {
+        let favourites = self.favourites.is_some_and(|f| layout.has(f));
+        reader.mark_array(field, if favourites {10} else {9});
+        let mut bank: Vec<Item> = Vec::with_capacity(BANK_SLOTS);
+        for _ in 0..BANK_SLOTS {
+            let id = i32::read(reader, field)?;
+            bank.push(Item {
+                id,
+                stack: i32::read(reader, field)?,
+                prefix: u8::read(reader, field)?,
+                favourites: if favourites {bool::read(reader, field)?} else {false},
+                unknown: Item::is_unknown_id(id),
+            });
+        }
+        return Ok(bank);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Vec<Item>, layout: &Layout, field: &'static str) -> Result<(), PlrError> {
+        if value.len() != BANK_SLOTS {
+            return Err(PlrError::InvalidField {
+                field,
+                reason: format!("expected {} slots, found {}", BANK_SLOTS, value.len()),
+            });
+        }
+        let favourites = self.favourites.is_some_and(|f| layout.has(f));
+        for item in value.iter() {
+            item.id.write(writer)?;
+            item.stack.write(writer)?;
+            item.prefix.write(writer)?;
+            if favourites {
+                item.favourites.write(writer)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+// Trailing empty slots are dropped on read and padded back on write
+pub(crate) struct Buffs;
+
+impl Codec<Vec<Buff>> for Buffs {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, layout: &Layout, field: &'static str) -> Result<Vec<Buff>, PlrError> {
+        reader.mark_array(field, 8);
+        let mut buffs: Vec<Buff> = Vec::with_capacity(layout.buff_slots());
+        for _ in 0..layout.buff_slots() {
+            buffs.push(Buff {
+                buff_type: i32::read(reader, field)?,
+                time: i32::read(reader, field)?,
+            });
+        }
+        while buffs.last().is_some_and(|b| b.is_empty()) {
+            buffs.pop();
+        }
+        return Ok(buffs);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Vec<Buff>, layout: &Layout, field: &'static str) -> Result<(), PlrError> {
+        if value.len() > layout.buff_slots() {
+            return Err(PlrError::InvalidField {
+                field,
+                reason: format!("{} buffs given but only {} slots are saved", value.len(), layout.buff_slots()),
+            });
+        }
+        for b in value.iter().chain(std::iter::repeat(&Buff::default())).take(layout.buff_slots()) {
+            b.buff_type.write(writer)?;
+            b.time.write(writer)?;
+        }
+        return Ok(());
+    }
+}
+
+// Records until an x of -1
+pub(crate) struct SpawnPoints;
+
+impl Codec<Vec<SpawnPoint>> for SpawnPoints {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, _layout: &Layout, field: &'static str) -> Result<Vec<SpawnPoint>, PlrError> {
+        let mut spawn_points: Vec<SpawnPoint> = Vec::new();
+        loop {
+            reader.mark_indexed(field, spawn_points.len());
+            let x = i32::read(reader, field)?;
+            if x == -1 {
+                return Ok(spawn_points);
+            }
+            spawn_points.push(SpawnPoint {
+                x,
+                y: i32::read(reader, field)?,
+                world_id: i32::read(reader, field)?,
+                world_name: String::read(reader, "spawn_points.world_name")?,
+            });
+        }
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Vec<SpawnPoint>, _layout: &Layout, _field: &'static str) -> Result<(), PlrError> {
+        for sp in value.iter() {
+            sp.x.write(writer)?;
+            sp.y.write(writer)?;
+            sp.world_id.write(writer)?;
+            sp.world_name.write(writer)?;
+        }
+        (-1i32).write(writer)?;
+        return Ok(());
+    }
+}
+
+// As many entries as the file version has builder accessories
+pub(crate) struct BuilderAccessories;
+
+impl Codec<Vec<i32>> for BuilderAccessories {
+    fn read<R: Read>(&self, reader: &mut CountingReader<R>, layout: &Layout, field: &'static str) -> Result<Vec<i32>, PlrError> {
+        reader.mark_array(field, 4);
+        let mut status: Vec<i32> = Vec::with_capacity(layout.builder_accessory_slots());
+        for _ in 0..layout.builder_accessory_slots() {
+            status.push(i32::read(reader, field)?);
+        }
+        return Ok(status);
+    }
+
+    fn write<W: Write>(&self, writer: &mut W, value: &Vec<i32>, layout: &Layout, field: &'static str) -> Result<(), PlrError> {
+        if value.len() != layout.builder_accessory_slots() {
+            return Err(PlrError::InvalidField {
+                field,
+                reason: format!("expected {} entries for version {}, found {}", layout.builder_accessory_slots(), layout.version, value.len()),
+            });
+        }
+        for status in value.iter() {
+            status.write(writer)?;
+        }
+        return Ok(());
+    }
+}
+
+// Generates `read_fields`/`write_fields` on `$target` from one ordered list of fields.
+// Each entry is `[#[since(Feature)]] path[.path][[index]] [=> codec];`, the codec defaulting to `Plain`.
+// Fields gated on a feature the file version lacks keep their `Default` value on read and are skipped on write.
+macro_rules! plr_layout {
+    (
+        $target:ty;
+        $(
+            $(#[since($feature:ident)])?
+            $($path:ident).+ $([$index:literal])? $(=> $codec:expr)?;
+        )*
+    ) => {
+        impl $target {
+            fn read_fields<R: Read>(&mut self, reader: &mut CountingReader<R>, layout: &Layout) -> Result<(), PlrError> {
+                $(
+                    if true $(&& layout.has(Feature::$feature))? {
+                        let field: &'static str = stringify!($($path).+);
+                        plr_layout!(@mark reader, field $(, $index)?);
+                        self.$($path).+ $([$index])? = Codec::read(&plr_layout!(@codec $($codec)?), reader, layout, field)?;
+                    }
+                )*
+                return Ok(());
+            }
+
+            fn write_fields<W: Write>(&self, writer: &mut W, layout: &Layout) -> Result<(), PlrError> {
+                $(
+                    if true $(&& layout.has(Feature::$feature))? {
+                        let field: &'static str = stringify!($($path).+);
+                        Codec::write(&plr_layout!(@codec $($codec)?), writer, &self.$($path).+ $([$index])?, layout, field)?;
+                    }
+                )*
+                return Ok(());
+            }
+
+            // write_fields into `data`, noting where each entry starts so tests can hold them against the read spans
+            #[cfg(test)]
+            fn write_fields_traced(&self, data: &mut Vec<u8>, layout: &Layout) -> Result<Vec<FieldSpan>, PlrError> {
+                let mut spans: Vec<FieldSpan> = Vec::new();
+                $(
+                    if true $(&& layout.has(Feature::$feature))? {
+                        let field: &'static str = stringify!($($path).+);
+                        spans.push(FieldSpan {
+                            offset: data.len() as u64,
+                            field,
+                            index: plr_layout!(@index $($index)?),
+                            element_size: None,
+                            element_path: None,
+                        });
+                        Codec::write(&plr_layout!(@codec $($codec)?), data, &self.$($path).+ $([$index])?, layout, field)?;
+                    }
+                )*
+                return Ok(spans);
+            }
+        }
+    };
+    (@index) => {
+        None
+    };
+    (@index $index:literal) => {
+        Some($index)
+    };
+    (@mark $reader:ident, $field:ident) => {
+        $reader.mark($field)
+    };
+    (@mark $reader:ident, $field:ident, $index:literal) => {
+        $reader.mark_indexed($field, $index)
+    };
+    (@codec) => {
+        $crate::schema::Plain
+    };
+    (@codec $codec:expr) => {
+        $codec
+    };
+}
+
+pub(crate) use plr_layout;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(powers: CreativePowers) -> (CreativePowers, Vec<u8>) {
+        let mut data: Vec<u8> = Vec::new();
+        powers.write(&mut data).unwrap();
+        let mut reader = CountingReader::new(data.as_slice());
+        let read = CreativePowers::read(&mut reader, "creative_powers").unwrap();
+        assert!(PlrUnpacker::r_to_end(&mut reader).unwrap().is_empty(), "{:?} left bytes unread", powers);
+        return (read, data);
+    }
+
+    #[test]
+    fn creative_powers_round_trip() {
+        let (read, data) = round_trip(CreativePowers::default());
+        assert_eq!(read, CreativePowers::default());
+        assert_eq!(data, [0]);
+
+        let single = [
+            CreativePowers { godmode: Some(true), ..CreativePowers::default() },
+            CreativePowers { far_placement_range: Some(false), ..CreativePowers::default() },
+            CreativePowers { spawn_rate: Some(0.75), ..CreativePowers::default() },
+        ];
+        for powers in single {
+            assert_eq!(round_trip(powers).0, powers);
+        }
+
+        // 0.1 has no exact f32 form, so any lossy conversion on the way would show in the bits
+        let spawn_rate = 0.1f32;
+        let all = CreativePowers { godmode: Some(false), far_placement_range: Some(true), spawn_rate: Some(spawn_rate) };
+        let (read, data) = round_trip(all);
+        assert_eq!(read, all);
+        assert_eq!(read.spawn_rate.map(f32::to_bits), Some(spawn_rate.to_bits()));
+        let mut expected = vec![1, 5, 0, 0, 1, 11, 0, 1, 1, 14, 0];
+        expected.extend_from_slice(&spawn_rate.to_le_bytes());
+        expected.push(0);
+        assert_eq!(data, expected);
+    }
+
+    #[test]
+    fn unknown_creative_power_is_an_error() {
+        let data = [1u8, 99, 0, 0, 0];
+        let mut reader = CountingReader::new(data.as_slice());
+        assert!(matches!(CreativePowers::read(&mut reader, "creative_powers"), Err(PlrError::InvalidField { .. })));
+    }
+}
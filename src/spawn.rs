@@ -0,0 +1,47 @@
+use crate::edit_plr::{
+    Plr,
+    SpawnPoint,
+};
+use crate::error::PlrError;
+
+// Which world's spawn records to act on. Names are not unique, so the world ID can be given instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum World {
+    Name(String),
+    Id(i32),
+}
+
+impl World {
+    pub fn matches(&self, spawn_point: &SpawnPoint) -> bool {
+        return match self {
+            World::Name(name) => spawn_point.world_name == *name,
+            World::Id(id) => spawn_point.world_id == *id,
+        };
+    }
+}
+
+impl Plr {
+    // Drops every bed spawn, so the player starts at each world's default spawn; returns how many were removed
+    pub fn clear_spawn_points(&mut self) -> usize {
+        let removed = self.spawn_points.len();
+        self.spawn_points.clear();
+        return removed;
+    }
+
+    // Drops the bed spawn for one world, e.g. for a player stuck at a broken bed; returns how many were removed
+    pub fn reset_spawn_point(&mut self, world: &World) -> Result<usize, PlrError> {
+        let before = self.spawn_points.len();
+        self.spawn_points.retain(|sp| !world.matches(sp));
+        let removed = before - self.spawn_points.len();
+        if removed == 0 {
+            return Err(PlrError::InvalidField {
+                field: "spawn_points",
+                reason: format!("no spawn point for world {}", match world {
+                    World::Name(name) => format!("{:?}", name),
+                    World::Id(id) => format!("ID {}", id),
+                }),
+            });
+        }
+        return Ok(removed);
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::edit_plr::{
+    Item,
+    Plr,
+    INVENTORY_SLOTS,
+    MAX_ITEM_ID,
+};
+use crate::error::PlrError;
+use crate::layout::{
+    Feature,
+    Layout,
+};
+
+pub const BANK_SLOTS: usize = 40;
+
+// The largest stack the game allows for any item since 1.4.4
+pub const MAX_STACK: i32 = 9999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bank {
+    PiggyBank,
+    Safe,
+    DefendersForge,
+    VoidVault,
+}
+
+impl Bank {
+    pub const ALL: [Bank; 4] = [Bank::PiggyBank, Bank::Safe, Bank::DefendersForge, Bank::VoidVault];
+
+    pub fn field(&self) -> &'static str {
+        return match self {
+            Bank::PiggyBank => "piggy_bank",
+            Bank::Safe => "safe",
+            Bank::DefendersForge => "defenders_forge",
+            Bank::VoidVault => "void_vault",
+        };
+    }
+
+    // Whether players of this layout have the bank at all
+    pub fn exists(&self, layout: &Layout) -> bool {
+        return match self {
+            Bank::PiggyBank | Bank::Safe => true,
+            Bank::DefendersForge => layout.has(Feature::DefendersForge),
+            Bank::VoidVault => layout.has(Feature::VoidVault),
+        };
+    }
+}
+
+impl FromStr for Bank {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "piggy-bank" => Ok(Bank::PiggyBank),
+            "safe" => Ok(Bank::Safe),
+            "defenders-forge" => Ok(Bank::DefendersForge),
+            "void-vault" => Ok(Bank::VoidVault),
+            _ => Err(format!("Unknown bank {:?} (expected piggy-bank, safe, defenders-forge or void-vault)", s)),
+        };
+    }
+}
+
+impl fmt::Display for Bank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(match self {
+            Bank::PiggyBank => "piggy-bank",
+            Bank::Safe => "safe",
+            Bank::DefendersForge => "defenders-forge",
+            Bank::VoidVault => "void-vault",
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Inventory,
+    Bank(Bank),
+}
+
+impl FromStr for Container {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "inventory" {
+            return Ok(Container::Inventory);
+        }
+        return s.parse().map(Container::Bank).map_err(|_| format!("Unknown container {:?} (expected inventory or a bank name)", s));
+    }
+}
+
+impl Container {
+    pub fn slots(&self) -> usize {
+        return match self {
+            Container::Inventory => INVENTORY_SLOTS,
+            Container::Bank(_) => BANK_SLOTS,
+        };
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Container::Inventory => f.write_str("inventory"),
+            Container::Bank(bank) => bank.fmt(f),
+        };
+    }
+}
+
+// `container` or `container:slot`, e.g. `inventory:12` or `void-vault`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub container: Container,
+    pub slot: Option<usize>,
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (container, slot) = match s.split_once(':') {
+            Some((container, slot)) => (container, Some(slot.parse().map_err(|_| format!("Invalid slot {:?} in {:?}", slot, s))?)),
+            None => (s, None),
+        };
+        return Ok(Location { container: container.parse()?, slot });
+    }
+}
+
+fn invalid(field: &'static str, reason: String) -> PlrError {
+    return PlrError::InvalidField { field, reason };
+}
+
+// An item the game can load: a known ID with a stack it allows, or an empty slot
+pub fn check_item(item: &Item, field: &'static str) -> Result<(), PlrError> {
+    if item.id == 0 {
+        if item.stack != 0 {
+            return Err(invalid(field, format!("empty slot with a stack of {}", item.stack)));
+        }
+        return Ok(());
+    }
+    if item.id < 0 || item.id > MAX_ITEM_ID {
+        return Err(invalid(field, format!("item ID {} is not between 1 and {}", item.id, MAX_ITEM_ID)));
+    }
+    if item.stack < 1 || item.stack > MAX_STACK {
+        return Err(invalid(field, format!("stack of {} is not between 1 and {}", item.stack, MAX_STACK)));
+    }
+    return Ok(());
+}
+
+impl Plr {
+    fn check_bank(&self, bank: Bank) -> Result<(), PlrError> {
+        if !bank.exists(&Layout::for_version(self.version)?) {
+            return Err(invalid(bank.field(), format!("version {} players have no {}", self.version, bank)));
+        }
+        return Ok(());
+    }
+
+    pub fn bank(&self, bank: Bank) -> Result<&Vec<Item>, PlrError> {
+        self.check_bank(bank)?;
+        return Ok(match bank {
+            Bank::PiggyBank => &self.piggy_bank,
+            Bank::Safe => &self.safe,
+            Bank::DefendersForge => &self.defenders_forge,
+            Bank::VoidVault => &self.void_vault,
+        });
+    }
+
+    pub fn bank_mut(&mut self, bank: Bank) -> Result<&mut Vec<Item>, PlrError> {
+        self.check_bank(bank)?;
+        return Ok(match bank {
+            Bank::PiggyBank => &mut self.piggy_bank,
+            Bank::Safe => &mut self.safe,
+            Bank::DefendersForge => &mut self.defenders_forge,
+            Bank::VoidVault => &mut self.void_vault,
+        });
+    }
+
+    fn container_field(container: Container) -> &'static str {
+        return match container {
+            Container::Inventory => "inventory",
+            Container::Bank(bank) => bank.field(),
+        };
+    }
+
+    fn container_slots(&mut self, container: Container) -> Result<Vec<&mut Item>, PlrError> {
+        return match container {
+            Container::Inventory => Ok(self.inventory.iter_mut().collect()),
+            Container::Bank(bank) => Ok(self.bank_mut(bank)?.iter_mut().collect()),
+        };
+    }
+
+    pub fn item_at(&mut self, container: Container, slot: usize) -> Result<&mut Item, PlrError> {
+        let field = Plr::container_field(container);
+        return self
+            .container_slots(container)?
+            .into_iter()
+            .nth(slot)
+            .ok_or_else(|| invalid(field, format!("no slot {} (expected 0 to {})", slot, container.slots() - 1)));
+    }
+
+    fn first_empty_slot(&mut self, container: Container) -> Result<usize, PlrError> {
+        let field = Plr::container_field(container);
+        return self
+            .container_slots(container)?
+            .iter()
+            .position(|item| item.id == 0)
+            .ok_or_else(|| invalid(field, String::from("no empty slot")));
+    }
+
+    fn place_item(&mut self, container: Container, slot: Option<usize>, item: Item) -> Result<usize, PlrError> {
+        let field = Plr::container_field(container);
+        let slot = match slot {
+            Some(slot) => slot,
+            None => self.first_empty_slot(container)?,
+        };
+        let target = self.item_at(container, slot)?;
+        if target.id != 0 {
+            return Err(invalid(field, format!("slot {} already holds item {}", slot, target.id)));
+        }
+        *target = item;
+        return Ok(slot);
+    }
+
+    // Puts `item` in `slot`, or the first empty slot, which must be empty; returns the slot used
+    pub fn add_item(&mut self, container: Container, slot: Option<usize>, item: Item) -> Result<usize, PlrError> {
+        check_item(&item, Plr::container_field(container))?;
+        return self.place_item(container, slot, item);
+    }
+
+    // Empties `slot`, returning what it held
+    pub fn remove_item(&mut self, container: Container, slot: usize) -> Result<Item, PlrError> {
+        let target = self.item_at(container, slot)?;
+        return Ok(std::mem::take(target));
+    }
+
+    // Moves the item at `from` into `to`, which must be empty; without a slot the first empty one is used
+    pub fn move_item(&mut self, from: Container, from_slot: usize, to: Container, to_slot: Option<usize>) -> Result<usize, PlrError> {
+        let item = *self.item_at(from, from_slot)?;
+        if item.id == 0 {
+            return Err(invalid(Plr::container_field(from), format!("slot {} is empty", from_slot)));
+        }
+        self.remove_item(from, from_slot)?;
+        return match self.place_item(to, to_slot, item) {
+            Ok(slot) => Ok(slot),
+            Err(e) => {
+                *self.item_at(from, from_slot)? = item;
+                Err(e)
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: i32, stack: i32) -> Item {
+        return Item { id, stack, prefix: 0, favourites: false, unknown: false };
+    }
+
+    fn player() -> Plr {
+        return Plr {
+            version: 279,
+            piggy_bank: vec![Item::default(); BANK_SLOTS],
+            safe: vec![Item::default(); BANK_SLOTS],
+            defenders_forge: vec![Item::default(); BANK_SLOTS],
+            void_vault: vec![Item::default(); BANK_SLOTS],
+            ..Plr::default()
+        };
+    }
+
+    #[test]
+    fn failed_move_puts_the_item_back() {
+        let mut plr = player();
+        plr.add_item(Container::Inventory, Some(12), item(8, 30)).unwrap();
+        plr.add_item(Container::Bank(Bank::Safe), Some(0), item(9, 1)).unwrap();
+
+        // Occupied target
+        assert!(plr.move_item(Container::Inventory, 12, Container::Bank(Bank::Safe), Some(0)).is_err());
+        assert_eq!(plr.item_at(Container::Inventory, 12).unwrap().id, 8);
+        assert_eq!(plr.item_at(Container::Bank(Bank::Safe), 0).unwrap().id, 9);
+
+        // Target slot out of range
+        assert!(plr.move_item(Container::Inventory, 12, Container::Bank(Bank::Safe), Some(BANK_SLOTS)).is_err());
+        assert_eq!(plr.item_at(Container::Inventory, 12).unwrap().stack, 30);
+
+        // Full target
+        for slot in plr.piggy_bank.iter_mut() {
+            *slot = item(1, 1);
+        }
+        assert!(plr.move_item(Container::Inventory, 12, Container::Bank(Bank::PiggyBank), None).is_err());
+        assert_eq!(plr.item_at(Container::Inventory, 12).unwrap().id, 8);
+
+        assert_eq!(plr.move_item(Container::Inventory, 12, Container::Bank(Bank::Safe), None).unwrap(), 1);
+        assert_eq!(plr.item_at(Container::Inventory, 12).unwrap().id, 0);
+        assert_eq!(plr.item_at(Container::Bank(Bank::Safe), 1).unwrap().id, 8);
+    }
+
+    #[test]
+    fn banks_missing_from_the_version_are_refused() {
+        let mut plr = Plr { version: 197, ..player() };
+        assert!(plr.bank(Bank::VoidVault).is_err());
+        assert!(plr.bank_mut(Bank::VoidVault).is_err());
+        assert!(plr.bank(Bank::DefendersForge).is_ok());
+        plr.version = 180;
+        assert!(plr.bank_mut(Bank::DefendersForge).is_err());
+    }
+}
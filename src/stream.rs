@@ -0,0 +1,268 @@
+use std::fmt;
+use std::io::{
+    self,
+    ErrorKind,
+    Read,
+    Write,
+};
+
+use aes::Aes128;
+use cipher::generic_array::GenericArray;
+use cipher::{
+    BlockDecryptMut,
+    BlockEncryptMut,
+    KeyIvInit,
+};
+
+use crate::error::PlrError;
+
+type Aes128CbcDec = cbc::Decryptor<Aes128>;
+type Aes128CbcEnc = cbc::Encryptor<Aes128>;
+
+const BLOCK_SIZE: usize = 16;
+
+// Carried inside io::Error so callers can tell a bad key apart from a failing source
+#[derive(Debug)]
+pub struct BadPadding;
+
+impl fmt::Display for BadPadding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str("Ciphertext is not block aligned or its padding is invalid");
+    }
+}
+
+impl std::error::Error for BadPadding {}
+
+pub fn is_bad_padding(e: &io::Error) -> bool {
+    return e.get_ref().is_some_and(|inner| inner.is::<BadPadding>());
+}
+
+// Decrypts AES-128-CBC on the fly, holding back one block so the PKCS#7 padding can be checked and stripped at EOF
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    cipher: Aes128CbcDec,
+    block: [u8; BLOCK_SIZE],
+    pos: usize,
+    len: usize,
+    pending: Option<[u8; BLOCK_SIZE]>,
+    eof: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    pub fn new(inner: R, key: &[u8]) -> Result<DecryptReader<R>, PlrError> {
+        let cipher = Aes128CbcDec::new_from_slices(key, key).map_err(|e| PlrError::Decryption(format!("Invalid key: {}", e)))?;
+        return Ok(DecryptReader {
+            inner,
+            cipher,
+            block: [0; BLOCK_SIZE],
+            pos: 0,
+            len: 0,
+            pending: None,
+            eof: false,
+        });
+    }
+
+    fn read_ciphertext_block(&mut self) -> io::Result<Option<[u8; BLOCK_SIZE]>> {
+        let mut block = [0; BLOCK_SIZE];
+        let mut filled = 0;
+        while filled < BLOCK_SIZE {
+            match self.inner.read(&mut block[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        return match filled {
+            0 => Ok(None),
+            BLOCK_SIZE => Ok(Some(block)),
+            _ => Err(io::Error::new(ErrorKind::InvalidData, BadPadding)),
+        };
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        while self.pos == self.len && !self.eof {
+            match self.read_ciphertext_block()? {
+                Some(mut block) => {
+                    self.cipher.decrypt_block_mut(GenericArray::from_mut_slice(&mut block));
+                    if let Some(previous) = self.pending.replace(block) {
+                        self.block = previous;
+                        self.pos = 0;
+                        self.len = BLOCK_SIZE;
+                    }
+                }
+                None => {
+                    self.eof = true;
+                    if let Some(last) = self.pending.take() {
+                        let n = last[BLOCK_SIZE - 1] as usize;
+                        if n == 0 || n > BLOCK_SIZE || last[BLOCK_SIZE - n..].iter().any(|b| *b as usize != n) {
+                            return Err(io::Error::new(ErrorKind::InvalidData, BadPadding));
+                        }
+                        self.block = last;
+                        self.pos = 0;
+                        self.len = BLOCK_SIZE - n;
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill()?;
+        let n = buf.len().min(self.len - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        return Ok(n);
+    }
+}
+
+// Encrypts AES-128-CBC on the fly; `finish` must be called to write the padded final block
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    cipher: Aes128CbcEnc,
+    block: [u8; BLOCK_SIZE],
+    len: usize,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    pub fn new(inner: W, key: &[u8]) -> Result<EncryptWriter<W>, PlrError> {
+        let cipher = Aes128CbcEnc::new_from_slices(key, key).map_err(|e| PlrError::Decryption(format!("Invalid key: {}", e)))?;
+        return Ok(EncryptWriter {
+            inner,
+            cipher,
+            block: [0; BLOCK_SIZE],
+            len: 0,
+        });
+    }
+
+    fn flush_block(&mut self) -> io::Result<()> {
+        self.cipher.encrypt_block_mut(GenericArray::from_mut_slice(&mut self.block));
+        self.inner.write_all(&self.block)?;
+        self.len = 0;
+        return Ok(());
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        let n = BLOCK_SIZE - self.len;
+        self.block[self.len..].fill(n as u8);
+        self.flush_block()?;
+        self.inner.flush()?;
+        return Ok(self.inner);
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(BLOCK_SIZE - self.len);
+        self.block[self.len..self.len + n].copy_from_slice(&buf[..n]);
+        self.len += n;
+        if self.len == BLOCK_SIZE {
+            self.flush_block()?;
+        }
+        return Ok(n);
+    }
+
+    // Only whole blocks can be written out; the partial one waits for more data or `finish`
+    fn flush(&mut self) -> io::Result<()> {
+        return self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_plr::{
+        decrypt_plr_aes128cbc,
+        encrypt_plr_aes128cbc,
+        Plr,
+    };
+    use crate::key::{
+        encode_key,
+        DEFAULT_KEY,
+    };
+
+    // Anything decrypt_plr_aes128cbc accepts has to start like a player file
+    fn plaintext(len: usize) -> Vec<u8> {
+        let mut data: Vec<u8> = (0..len).map(|i| (i * 7) as u8).collect();
+        data[4..11].copy_from_slice(b"relogic");
+        return data;
+    }
+
+    fn encrypt_streaming(data: &[u8], key: &[u8]) -> Vec<u8> {
+        let mut writer = EncryptWriter::new(Vec::new(), key).unwrap();
+        // Uneven writes so blocks get filled across calls
+        for chunk in data.chunks(5) {
+            writer.write_all(chunk).unwrap();
+        }
+        return writer.finish().unwrap();
+    }
+
+    fn decrypt_streaming(data: &[u8], key: &[u8]) -> io::Result<Vec<u8>> {
+        let mut plain: Vec<u8> = Vec::new();
+        DecryptReader::new(data, key).unwrap().read_to_end(&mut plain)?;
+        return Ok(plain);
+    }
+
+    // Encrypts `data` with a valid final padding block, then drops that block so whatever `data` ends with is taken
+    // as the padding
+    fn encrypt_unpadded(data: &[u8], key: &[u8]) -> Vec<u8> {
+        assert_eq!(data.len() % BLOCK_SIZE, 0);
+        let mut encrypted = encrypt_plr_aes128cbc(data.to_vec(), key).unwrap();
+        encrypted.truncate(data.len());
+        return encrypted;
+    }
+
+    #[test]
+    fn streaming_matches_buffered() {
+        let key = encode_key(DEFAULT_KEY);
+        for len in [11, 15, 16, 17, 31, 32, 33, 48, 64, 100, 1000] {
+            let data = plaintext(len);
+            let encrypted = encrypt_plr_aes128cbc(data.clone(), &key).unwrap();
+            assert_eq!(encrypt_streaming(&data, &key), encrypted, "length {}", len);
+            assert_eq!(encrypted.len(), (len / BLOCK_SIZE + 1) * BLOCK_SIZE, "length {}", len);
+            assert_eq!(decrypt_streaming(&encrypted, &key).unwrap(), data, "length {}", len);
+            assert_eq!(decrypt_plr_aes128cbc(encrypted, &key).unwrap(), data, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn bad_padding_is_rejected() {
+        let key = encode_key(DEFAULT_KEY);
+        let mut zero = plaintext(32);
+        zero[31] = 0;
+        let mut too_long = plaintext(32);
+        too_long[31] = 17;
+        let mut mismatched = plaintext(32);
+        mismatched[28..].copy_from_slice(&[4, 4, 3, 4]);
+        for data in [zero, too_long, mismatched] {
+            let encrypted = encrypt_unpadded(&data, &key);
+            assert!(is_bad_padding(&decrypt_streaming(&encrypted, &key).unwrap_err()));
+            assert!(matches!(decrypt_plr_aes128cbc(encrypted, &key), Err(PlrError::WrongKey)));
+        }
+        let mut full_block = plaintext(32);
+        full_block[16..].fill(16);
+        let encrypted = encrypt_unpadded(&full_block, &key);
+        assert_eq!(decrypt_streaming(&encrypted, &key).unwrap(), &full_block[..16]);
+        assert_eq!(decrypt_plr_aes128cbc(encrypted, &key).unwrap(), &full_block[..16]);
+    }
+
+    #[test]
+    fn truncated_final_block_is_rejected() {
+        let key = encode_key(DEFAULT_KEY);
+        let mut encrypted = encrypt_plr_aes128cbc(plaintext(40), &key).unwrap();
+        encrypted.pop();
+        assert!(is_bad_padding(&decrypt_streaming(&encrypted, &key).unwrap_err()));
+        assert!(matches!(decrypt_plr_aes128cbc(encrypted, &key), Err(PlrError::WrongKey)));
+    }
+
+    #[test]
+    fn wrong_key_is_reported() {
+        let encrypted = encrypt_plr_aes128cbc(plaintext(100), &encode_key(DEFAULT_KEY)).unwrap();
+        let wrong = encode_key("not_it!!");
+        assert!(matches!(decrypt_plr_aes128cbc(encrypted.clone(), &wrong), Err(PlrError::WrongKey)));
+        assert!(matches!(Plr::read_from(encrypted.as_slice(), &wrong), Err(PlrError::WrongKey)));
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{
+    Serialize,
+    Deserialize,
+};
+
+use crate::edit_plr::Plr;
+use crate::error::PlrError;
+use crate::layout::{
+    Feature,
+    Layout,
+};
+
+pub const BASE_LIFE: i32 = 100;
+pub const LIFE_PER_CRYSTAL: i32 = 20;
+pub const MAX_LIFE_CRYSTALS: i32 = 15;
+pub const LIFE_PER_FRUIT: i32 = 5;
+pub const MAX_LIFE_FRUITS: i32 = 20;
+pub const BASE_MANA: i32 = 20;
+pub const MANA_PER_CRYSTAL: i32 = 20;
+pub const MAX_MANA_CRYSTALS: i32 = 9;
+
+// Everything a player has permanently consumed or unlocked. Crystal and fruit counts are worked out from
+// `stat_life_max`/`stat_mana_max`; the flags live in their own `Plr` fields, named after the game's save code.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+)]
+pub struct PermanentUpgrades {
+    pub life_crystals: i32,
+    pub life_fruits: i32,
+    pub mana_crystals: i32,
+    pub vital_crystal: bool, // `used_aegis_crystal`
+    pub aegis_fruit: bool,
+    pub arcane_crystal: bool,
+    pub galaxy_pearl: bool,
+    pub gummy_worm: bool,
+    pub ambrosia: bool,
+    pub artisan_loaf: bool, // `ate_artisan_bread`
+    pub demon_heart: bool, // `extra_accessory`
+    pub minecart_upgrade_kit: bool, // `super_cart.unlocked`
+    pub torch_gods_favor: bool, // `unlocked_biome_torches`
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upgrade {
+    LifeCrystal,
+    LifeFruit,
+    ManaCrystal,
+    VitalCrystal,
+    AegisFruit,
+    ArcaneCrystal,
+    GalaxyPearl,
+    GummyWorm,
+    Ambrosia,
+    ArtisanLoaf,
+    DemonHeart,
+    MinecartUpgradeKit,
+    TorchGodsFavor,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 13] = [
+        Upgrade::LifeCrystal,
+        Upgrade::LifeFruit,
+        Upgrade::ManaCrystal,
+        Upgrade::VitalCrystal,
+        Upgrade::AegisFruit,
+        Upgrade::ArcaneCrystal,
+        Upgrade::GalaxyPearl,
+        Upgrade::GummyWorm,
+        Upgrade::Ambrosia,
+        Upgrade::ArtisanLoaf,
+        Upgrade::DemonHeart,
+        Upgrade::MinecartUpgradeKit,
+        Upgrade::TorchGodsFavor,
+    ];
+
+    fn name(&self) -> &'static str {
+        return match self {
+            Upgrade::LifeCrystal => "life-crystal",
+            Upgrade::LifeFruit => "life-fruit",
+            Upgrade::ManaCrystal => "mana-crystal",
+            Upgrade::VitalCrystal => "vital-crystal",
+            Upgrade::AegisFruit => "aegis-fruit",
+            Upgrade::ArcaneCrystal => "arcane-crystal",
+            Upgrade::GalaxyPearl => "galaxy-pearl",
+            Upgrade::GummyWorm => "gummy-worm",
+            Upgrade::Ambrosia => "ambrosia",
+            Upgrade::ArtisanLoaf => "artisan-loaf",
+            Upgrade::DemonHeart => "demon-heart",
+            Upgrade::MinecartUpgradeKit => "minecart-upgrade-kit",
+            Upgrade::TorchGodsFavor => "torch-gods-favor",
+        };
+    }
+
+    // Life crystals, life fruit and mana crystals are counted through the max life and mana rather than saved as flags
+    pub fn is_counted(&self) -> bool {
+        return matches!(self, Upgrade::LifeCrystal | Upgrade::LifeFruit | Upgrade::ManaCrystal);
+    }
+
+    // The file version feature needed to save the upgrade, if any
+    pub fn feature(&self) -> Option<Feature> {
+        return match self {
+            Upgrade::LifeCrystal | Upgrade::LifeFruit | Upgrade::ManaCrystal | Upgrade::DemonHeart => None,
+            Upgrade::VitalCrystal
+            | Upgrade::AegisFruit
+            | Upgrade::ArcaneCrystal
+            | Upgrade::GalaxyPearl
+            | Upgrade::GummyWorm
+            | Upgrade::Ambrosia => Some(Feature::PermanentConsumables),
+            Upgrade::ArtisanLoaf => Some(Feature::ArtisanBread),
+            Upgrade::MinecartUpgradeKit => Some(Feature::SuperCart),
+            Upgrade::TorchGodsFavor => Some(Feature::BiomeTorches),
+        };
+    }
+}
+
+impl FromStr for Upgrade {
+    type Err = String;
+
+    // The game's save code calls the Vital Crystal the Aegis Crystal, so that name is accepted too
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "aegis-crystal" {
+            return Ok(Upgrade::VitalCrystal);
+        }
+        return Upgrade::ALL
+            .into_iter()
+            .find(|u| u.name() == s)
+            .ok_or_else(|| format!("Unknown upgrade {:?} (expected one of {})", s, Upgrade::ALL.map(|u| u.name()).join(", ")));
+    }
+}
+
+impl fmt::Display for Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.write_str(self.name());
+    }
+}
+
+impl Plr {
+    pub fn permanent_upgrades(&self) -> PermanentUpgrades {
+        return PermanentUpgrades {
+            life_crystals: ((self.stat_life_max - BASE_LIFE) / LIFE_PER_CRYSTAL).clamp(0, MAX_LIFE_CRYSTALS),
+            life_fruits: ((self.stat_life_max - BASE_LIFE - MAX_LIFE_CRYSTALS * LIFE_PER_CRYSTAL) / LIFE_PER_FRUIT).clamp(0, MAX_LIFE_FRUITS),
+            mana_crystals: ((self.stat_mana_max - BASE_MANA) / MANA_PER_CRYSTAL).clamp(0, MAX_MANA_CRYSTALS),
+            vital_crystal: self.used_aegis_crystal,
+            aegis_fruit: self.used_aegis_fruit,
+            arcane_crystal: self.used_arcane_crystal,
+            galaxy_pearl: self.used_galaxy_pearl,
+            gummy_worm: self.used_gummy_worm,
+            ambrosia: self.used_ambrosia,
+            artisan_loaf: self.ate_artisan_bread,
+            demon_heart: self.extra_accessory,
+            minecart_upgrade_kit: self.super_cart.unlocked,
+            torch_gods_favor: self.unlocked_biome_torches,
+        };
+    }
+
+    pub fn has_upgrade(&self, upgrade: Upgrade) -> bool {
+        let u = self.permanent_upgrades();
+        return match upgrade {
+            Upgrade::LifeCrystal => u.life_crystals == MAX_LIFE_CRYSTALS,
+            Upgrade::LifeFruit => u.life_fruits == MAX_LIFE_FRUITS,
+            Upgrade::ManaCrystal => u.mana_crystals == MAX_MANA_CRYSTALS,
+            Upgrade::VitalCrystal => u.vital_crystal,
+            Upgrade::AegisFruit => u.aegis_fruit,
+            Upgrade::ArcaneCrystal => u.arcane_crystal,
+            Upgrade::GalaxyPearl => u.galaxy_pearl,
+            Upgrade::GummyWorm => u.gummy_worm,
+            Upgrade::Ambrosia => u.ambrosia,
+            Upgrade::ArtisanLoaf => u.artisan_loaf,
+            Upgrade::DemonHeart => u.demon_heart,
+            Upgrade::MinecartUpgradeKit => u.minecart_upgrade_kit,
+            Upgrade::TorchGodsFavor => u.torch_gods_favor,
+        };
+    }
+
+    fn set_life_max(&mut self, crystals: i32, fruits: i32) {
+        self.stat_life_max = BASE_LIFE + crystals * LIFE_PER_CRYSTAL + fruits * LIFE_PER_FRUIT;
+        self.stat_life = self.stat_life.min(self.stat_life_max);
+    }
+
+    // Granting a counted upgrade uses all of them; life fruit also needs every life crystal, as in game.
+    // Revoking life crystals takes the fruit with them.
+    pub fn set_upgrade(&mut self, upgrade: Upgrade, granted: bool) -> Result<(), PlrError> {
+        if let Some(feature) = upgrade.feature() {
+            if !Layout::for_version(self.version)?.has(feature) {
+                return Err(PlrError::InvalidField {
+                    field: "upgrades",
+                    reason: format!("version {} players cannot have the {}", self.version, upgrade),
+                });
+            }
+        }
+        let u = self.permanent_upgrades();
+        match upgrade {
+            Upgrade::LifeCrystal if granted => self.set_life_max(MAX_LIFE_CRYSTALS, u.life_fruits),
+            Upgrade::LifeCrystal => self.set_life_max(0, 0),
+            Upgrade::LifeFruit if granted => self.set_life_max(MAX_LIFE_CRYSTALS, MAX_LIFE_FRUITS),
+            Upgrade::LifeFruit => self.set_life_max(u.life_crystals, 0),
+            Upgrade::ManaCrystal => {
+                self.stat_mana_max = BASE_MANA + if granted {MAX_MANA_CRYSTALS * MANA_PER_CRYSTAL} else {0};
+                self.stat_mana = self.stat_mana.min(self.stat_mana_max);
+            }
+            Upgrade::VitalCrystal => self.used_aegis_crystal = granted,
+            Upgrade::AegisFruit => self.used_aegis_fruit = granted,
+            Upgrade::ArcaneCrystal => self.used_arcane_crystal = granted,
+            Upgrade::GalaxyPearl => self.used_galaxy_pearl = granted,
+            Upgrade::GummyWorm => self.used_gummy_worm = granted,
+            Upgrade::Ambrosia => self.used_ambrosia = granted,
+            Upgrade::ArtisanLoaf => self.ate_artisan_bread = granted,
+            Upgrade::DemonHeart => self.extra_accessory = granted,
+            Upgrade::MinecartUpgradeKit => {
+                self.super_cart.unlocked = granted;
+                self.super_cart.enabled &= granted;
+            }
+            Upgrade::TorchGodsFavor => {
+                self.unlocked_biome_torches = granted;
+                self.using_biome_torches &= granted;
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(stat_life_max: i32, stat_mana_max: i32) -> (i32, i32, i32) {
+        let plr = Plr { stat_life_max, stat_mana_max, ..Plr::default() };
+        let u = plr.permanent_upgrades();
+        return (u.life_crystals, u.life_fruits, u.mana_crystals);
+    }
+
+    #[test]
+    fn counts_follow_max_life_and_mana() {
+        assert_eq!(counts(100, 20), (0, 0, 0));
+        assert_eq!(counts(260, 120), (8, 0, 5));
+        // Partial steps round down
+        assert_eq!(counts(259, 139), (7, 0, 5));
+        assert_eq!(counts(400, 200), (15, 0, 9));
+        assert_eq!(counts(450, 200), (15, 10, 9));
+        assert_eq!(counts(500, 200), (15, 20, 9));
+        // Values outside what the game allows are clamped
+        assert_eq!(counts(50, 0), (0, 0, 0));
+        assert_eq!(counts(9999, 9999), (15, 20, 9));
+    }
+
+    #[test]
+    fn counted_upgrades_set_max_life_and_mana() {
+        let mut plr = Plr { stat_life: 300, stat_life_max: 300, stat_mana: 200, stat_mana_max: 200, ..Plr::default() };
+        plr.set_upgrade(Upgrade::LifeFruit, true).unwrap();
+        assert_eq!(plr.stat_life_max, 500);
+        assert!(plr.has_upgrade(Upgrade::LifeCrystal) && plr.has_upgrade(Upgrade::LifeFruit));
+        plr.set_upgrade(Upgrade::LifeFruit, false).unwrap();
+        assert_eq!(plr.stat_life_max, 400);
+        plr.stat_life_max = 450;
+        plr.set_upgrade(Upgrade::LifeCrystal, true).unwrap();
+        assert_eq!(plr.stat_life_max, 450);
+        // Revoking crystals takes the fruit too and the current life drops to the new max
+        plr.set_upgrade(Upgrade::LifeCrystal, false).unwrap();
+        assert_eq!((plr.stat_life_max, plr.stat_life), (100, 100));
+
+        plr.set_upgrade(Upgrade::ManaCrystal, false).unwrap();
+        assert_eq!((plr.stat_mana_max, plr.stat_mana), (20, 20));
+        plr.set_upgrade(Upgrade::ManaCrystal, true).unwrap();
+        assert_eq!((plr.stat_mana_max, plr.stat_mana), (200, 20));
+    }
+
+    #[test]
+    fn upgrades_parse_by_name() {
+        for upgrade in Upgrade::ALL {
+            assert_eq!(upgrade.name().parse::<Upgrade>(), Ok(upgrade));
+        }
+        assert_eq!("aegis-crystal".parse::<Upgrade>(), Ok(Upgrade::VitalCrystal));
+        assert!("heart-crystal".parse::<Upgrade>().is_err());
+        assert_eq!(Upgrade::ALL.into_iter().filter(|u| u.is_counted()).count(), 3);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::edit_plr::{
+    decrypt_plr_aes128cbc,
+    deserialize_plr_traced,
+    encrypt_plr_aes128cbc,
+    serialize_struct_to_raw_plr,
+    FieldSpan,
+};
+use crate::error::PlrError;
+
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub offset: u64, // Into the decrypted data
+    pub field: String,
+    pub original: Option<u8>, // None when the original ends before this offset
+    pub rewritten: Option<u8>, // None when the rewritten data ends before this offset
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyReport {
+    pub original_len: usize,
+    pub rewritten_len: usize,
+    pub mismatch: Option<Mismatch>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        return self.mismatch.is_none();
+    }
+}
+
+// Name of the field the byte at `offset` belongs to, given the spans recorded while parsing
+pub fn locate_field(spans: &[FieldSpan], offset: u64) -> String {
+    return match spans.iter().rev().find(|s| s.offset <= offset) {
+        Some(span) => span.describe(offset),
+        None => String::from("<before version>"),
+    };
+}
+
+// Decrypts, parses, serializes and re-encrypts `plr_file`, reporting the first decrypted byte that did not survive
+pub fn verify_plr(plr_file: &[u8], key: &[u8]) -> Result<VerifyReport, PlrError> {
+    let original = decrypt_plr_aes128cbc(plr_file.to_vec(), key)?;
+    let (plr, spans) = deserialize_plr_traced(&original)?;
+    let rewritten = serialize_struct_to_raw_plr(&plr)?;
+
+    let mismatch = (0..original.len().max(rewritten.len()))
+        .find(|i| original.get(*i) != rewritten.get(*i))
+        .map(|i| Mismatch {
+            offset: i as u64,
+            field: locate_field(&spans, i as u64),
+            original: original.get(i).copied(),
+            rewritten: rewritten.get(i).copied(),
+        });
+
+    // Identical plaintext always encrypts to the same file, so this only guards against a broken cipher round trip
+    if mismatch.is_none() && encrypt_plr_aes128cbc(rewritten.clone(), key)? != plr_file {
+        return Err(PlrError::Decryption(String::from("Re-encrypting the unchanged data did not reproduce the original file")));
+    }
+
+    return Ok(VerifyReport {
+        original_len: original.len(),
+        rewritten_len: rewritten.len(),
+        mismatch,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_plr::tests::sample;
+    use crate::key::{
+        encode_key,
+        DEFAULT_KEY,
+    };
+    use crate::layout::MAX_SUPPORTED_VERSION;
+
+    // Sets the byte `at` bytes into `field` to 2, a bool value that is read as true and written back as 1
+    fn mismatch_in(field: &str, at: u64) -> Mismatch {
+        let key = encode_key(DEFAULT_KEY);
+        let mut data = serialize_struct_to_raw_plr(&sample(MAX_SUPPORTED_VERSION)).unwrap();
+        let (_, spans) = deserialize_plr_traced(&data).unwrap();
+        let offset = spans.iter().find(|s| s.field == field).unwrap().offset + at;
+        data[offset as usize] = 2;
+        let report = verify_plr(&encrypt_plr_aes128cbc(data, &key).unwrap(), &key).unwrap();
+        let mismatch = report.mismatch.unwrap();
+        assert_eq!(mismatch.offset, offset);
+        assert_eq!(mismatch.original, Some(2));
+        assert_eq!(mismatch.rewritten, Some(1));
+        return mismatch;
+    }
+
+    #[test]
+    fn unchanged_player_verifies() {
+        let key = encode_key(DEFAULT_KEY);
+        let data = serialize_struct_to_raw_plr(&sample(MAX_SUPPORTED_VERSION)).unwrap();
+        assert!(verify_plr(&encrypt_plr_aes128cbc(data, &key).unwrap(), &key).unwrap().is_ok());
+    }
+
+    #[test]
+    fn mismatch_names_the_nested_element() {
+        // The favourite flag of row 2, slot 3
+        assert_eq!(mismatch_in("inventory.main", 2 * 100 + 3 * 10 + 9).field, "inventory.main[2][3]");
+        assert_eq!(mismatch_in("hide_info", 4).field, "hide_info[4]");
+        assert_eq!(mismatch_in("loadouts", 280 + 270 + 4).field, "loadouts[1].hide_visual[4]");
+    }
+
+    #[test]
+    fn nested_offsets_are_described_by_slot() {
+        let (_, spans) = deserialize_plr_traced(&serialize_struct_to_raw_plr(&sample(MAX_SUPPORTED_VERSION)).unwrap()).unwrap();
+        let loadouts = spans.iter().find(|s| s.field == "loadouts").unwrap().offset;
+        assert_eq!(locate_field(&spans, loadouts + 280 + 9), "loadouts[1].armor.body");
+        assert_eq!(locate_field(&spans, loadouts + 280 + 5 * 9), "loadouts[1].armor.accessories[2]");
+        assert_eq!(locate_field(&spans, loadouts + 2 * 280 + 180 + 4 * 9 + 8), "loadouts[2].dyes.accessories[1]");
+        let main = spans.iter().find(|s| s.field == "inventory.main").unwrap().offset;
+        assert_eq!(locate_field(&spans, main + 3 * 100 + 99), "inventory.main[3][9]");
+    }
+}